@@ -17,7 +17,8 @@
 
 #![no_main]
 
-use optee_utee::net::TcpStream;
+use optee_utee::time::TimeSource;
+use optee_utee::tls::{self, rustls, TlsStream};
 use optee_utee::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
 };
@@ -62,30 +63,20 @@ fn invoke_command(cmd_id: u32, _params: &mut Parameters) -> Result<()> {
 }
 
 fn tls_client() {
-    let mut config = rustls::ClientConfig::new();
-    config
-        .root_store
-        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
-    let rc_config = Arc::new(config);
-    let dns_name = webpki::DNSNameRef::try_from_ascii_str("google.com").unwrap();
-    let mut conn = rustls::ClientSession::new(&rc_config, dns_name);
-    let mut sock = TcpStream::connect("google.com", 443).unwrap();
-    let mut tls = rustls::Stream::new(&mut conn, &mut sock);
-    tls.write_all(b"GET / HTTP/1.0\r\nHost: google.com\r\nAccept-Encoding: identity\r\n\r\n")
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    // The example does not provision the TA persistent time, so trust the REE time.
+    let config = Arc::new(tls::client_config(roots, TimeSource::Ree));
+    let mut stream = TlsStream::connect("google.com", 443, &config).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.0\r\nHost: google.com\r\nAccept-Encoding: identity\r\n\r\n")
         .unwrap();
-    tls.flush().unwrap();
+    stream.flush().unwrap();
 
     let mut response = Vec::new();
-    let mut chunk = [0u8; 1024];
-    loop {
-        match tls.read(&mut chunk) {
-            Ok(0) => break,
-            Ok(n) => response.extend_from_slice(&chunk[..n]),
-            Err(_) => {
-                trace_println!("Error");
-                panic!();
-            }
-        }
+    if stream.read_to_end(&mut response).is_err() {
+        trace_println!("Error");
+        panic!();
     }
     trace_println!("{}", String::from_utf8_lossy(&response));
 }
@@ -17,15 +17,15 @@
 
 #![no_main]
 
+use optee_utee::tls::{self, rustls, TeeSigningKey};
 use optee_utee::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
 };
-use optee_utee::{Error, ErrorKind, Parameters, Result};
+use optee_utee::{Error, ErrorKind, Parameters, Result, TransientObject};
 use proto::Command;
 
 use lazy_static::lazy_static;
-use rustls;
-use rustls::{NoClientAuth, Session};
+use rustls::Session;
 use std::collections::HashMap;
 use std::io::Cursor;
 use std::io::{BufReader, Read, Write};
@@ -136,16 +136,22 @@ pub fn do_tls_write(session_id: u32, buf: &mut [u8]) -> usize {
     rc
 }
 
+const KEY_ID: &[u8] = b"tls-server-key";
+
 fn make_config() -> Arc<rustls::ServerConfig> {
-    let client_auth = NoClientAuth::new();
-    let mut tls_config = rustls::ServerConfig::new(client_auth);
-    let certs = load_certs();
-    let privkey = load_private_key();
-    tls_config
-        .set_single_cert(certs, privkey)
-        .expect("bad certificates/private key");
-
-    Arc::new(tls_config)
+    let key = match TeeSigningKey::new(KEY_ID) {
+        Ok(key) => key,
+        // Provision the private storage with the test key on first use, the key then never
+        // leaves the TEE.
+        Err(e) if e.kind() == ErrorKind::ItemNotFound => {
+            let pem = include_str!("../test-ca/ecdsa/end.key");
+            let object = TransientObject::from_pem(pem).expect("invalid private key");
+            TeeSigningKey::import(KEY_ID, &object).expect("cannot store private key")
+        }
+        Err(e) => panic!("cannot open private key: {}", e),
+    };
+
+    Arc::new(tls::server_config(load_certs(), key))
 }
 
 fn load_certs() -> Vec<rustls::Certificate> {
@@ -155,32 +161,6 @@ fn load_certs() -> Vec<rustls::Certificate> {
     rustls::internal::pemfile::certs(&mut reader).unwrap()
 }
 
-fn load_private_key() -> rustls::PrivateKey {
-    let bytes = include_bytes!("../test-ca/ecdsa/end.key").to_vec();
-
-    let rsa_keys = {
-        let cursor = std::io::Cursor::new(bytes.clone());
-        let mut reader = BufReader::new(cursor);
-        rustls::internal::pemfile::rsa_private_keys(&mut reader)
-            .expect("file contains invalid rsa private key")
-    };
-
-    let pkcs8_keys = {
-        let cursor = std::io::Cursor::new(bytes);
-        let mut reader = BufReader::new(cursor);
-        rustls::internal::pemfile::pkcs8_private_keys(&mut reader)
-            .expect("file contains invalid pkcs8 private key (encrypted keys not supported)")
-    };
-
-    // prefer to load pkcs8 keys
-    if !pkcs8_keys.is_empty() {
-        pkcs8_keys[0].clone()
-    } else {
-        assert!(!rsa_keys.is_empty());
-        rsa_keys[0].clone()
-    }
-}
-
 // TA configurations
 const TA_FLAGS: u32 = 0;
 const TA_DATA_SIZE: u32 = 18 * 1024 * 1024;
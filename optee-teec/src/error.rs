@@ -16,6 +16,7 @@
 // under the License.
 
 use optee_teec_sys as raw;
+use std::convert::TryFrom;
 use std::fmt;
 
 /// A specialized [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
@@ -30,6 +31,11 @@ use std::fmt;
 /// ````
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The first code of the range left to application-defined errors of a TA.
+pub const APP_ERROR_MIN: u32 = 0x0000_0001;
+/// The last code of the range left to application-defined errors of a TA.
+pub const APP_ERROR_MAX: u32 = 0x7FFF_FFFF;
+
 /// The error type for TEE operations of [`Context`] and [`Session`].
 ///
 /// [`Context`]: struct.Context.html
@@ -83,9 +89,21 @@ pub enum ErrorKind {
     TargetDead = 0xFFFF3024,
     /// Unknown error.
     Unknown,
+    /// Application-defined error returned by the TA, see [AppError](struct.AppError.html). The
+    /// kind covers a range of codes and has no code of its own: [Error::new](Error::new) and
+    /// `From` give a `Generic` error for it.
+    Application,
 }
 
 impl ErrorKind {
+    // The code of an error of this kind, `Generic` for the application-defined errors.
+    fn code(self) -> u32 {
+        match self {
+            ErrorKind::Application => raw::TEEC_ERROR_GENERIC,
+            kind => kind as u32,
+        }
+    }
+
     pub(crate) fn as_str(&self) -> &'static str {
         match *self {
             ErrorKind::Generic => "Non-specific cause.",
@@ -112,13 +130,16 @@ impl ErrorKind {
             ErrorKind::ExternalCancel => "Undocumented.",
             ErrorKind::TargetDead => "Trusted Application has panicked during the operation.",
             ErrorKind::Unknown => "Unknown error.",
+            ErrorKind::Application => "Application-defined error.",
         }
     }
 }
 
 impl Error {
+    /// Creates an error of `kind`. `ErrorKind::Application` gives a `Generic` error, the
+    /// application-defined errors come from the TA.
     pub fn new(kind: ErrorKind) -> Error {
-        Error { code: kind as u32 }
+        Error { code: kind.code() }
     }
     /// Creates a new instance of an `Error` from a particular TEE error code.
     ///
@@ -164,6 +185,7 @@ impl Error {
             raw::TEEC_ERROR_SHORT_BUFFER => ErrorKind::ShortBuffer,
             raw::TEEC_ERROR_EXTERNAL_CANCEL => ErrorKind::ExternalCancel,
             raw::TEEC_ERROR_TARGET_DEAD => ErrorKind::TargetDead,
+            APP_ERROR_MIN..=APP_ERROR_MAX => ErrorKind::Application,
             _ => ErrorKind::Unknown,
         }
     }
@@ -173,6 +195,14 @@ impl Error {
         self.code
     }
 
+    /// Returns the code of an application-defined error, `None` for the other errors.
+    pub fn app_code(&self) -> Option<u32> {
+        match self.code {
+            APP_ERROR_MIN..=APP_ERROR_MAX => Some(self.code),
+            _ => None,
+        }
+    }
+
     /// Returns corresponding error message of this error.
     pub fn message(&self) -> &str {
         self.kind().as_str()
@@ -200,6 +230,99 @@ impl std::error::Error for Error {
 impl From<ErrorKind> for Error {
     #[inline]
     fn from(kind: ErrorKind) -> Error {
-        Error { code: kind as u32 }
+        Error::new(kind)
+    }
+}
+
+/// An application-defined error of a TA, with the message sent along with it.
+///
+/// The TA returns `optee_utee::Error::app(code).with_detail(message)` from an entry point
+/// declared with `#[ta_invoke_command(error_message = N)]`, which writes the message to the
+/// output memref `N` of the command.
+///
+/// # Examples
+///
+/// ``` no_run
+/// #[derive(Debug)]
+/// enum KeyError {
+///     QuotaExceeded,
+/// }
+///
+/// impl TryFrom<u32> for KeyError {
+///     type Error = ();
+///     fn try_from(code: u32) -> std::result::Result<Self, ()> {
+///         match code {
+///             0x1001 => Ok(KeyError::QuotaExceeded),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// let mut message = [0u8; 256];
+/// let p3 = ParamTmpRef::new_output(&mut message);
+/// let mut operation = Operation::new(0, ParamNone, ParamNone, ParamNone, p3);
+/// if let Err(e) = session.invoke_command(CMD_GENERATE_KEY, &mut operation) {
+///     let len = operation.parameters().3.updated_size();
+///     let error = AppError::<KeyError>::decode(e, &message[..len])?;
+///     println!("{}", error);
+/// }
+/// ```
+pub struct AppError<E> {
+    code: E,
+    message: Option<String>,
+}
+
+impl<E: TryFrom<u32>> AppError<E> {
+    /// Decodes the error returned by a command and the content of the memref designated for
+    /// its message.
+    ///
+    /// # Errors
+    ///
+    /// 1) The original `error`: If its code is not in the application-defined range or not
+    ///    accepted by `E`.
+    pub fn decode(error: Error, message: &[u8]) -> std::result::Result<AppError<E>, Error> {
+        let code = match error.app_code().map(E::try_from) {
+            Some(Ok(code)) => code,
+            _ => return Err(error),
+        };
+        let message = match message.len() {
+            0 => None,
+            _ => Some(String::from_utf8_lossy(message).into_owned()),
+        };
+        Ok(AppError { code, message })
+    }
+}
+
+impl<E> AppError<E> {
+    /// Returns the typed code of this error.
+    pub fn code(&self) -> &E {
+        &self.code
+    }
+
+    /// Returns the message sent by the TA, `None` if it sent none.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Returns the typed code and the message of this error.
+    pub fn into_parts(self) -> (E, Option<String>) {
+        (self.code, self.message)
     }
 }
+
+impl<E: fmt::Debug> fmt::Debug for AppError<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, fmt)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for AppError<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(fmt, "{:?}: {}", self.code, message),
+            None => write!(fmt, "{:?}", self.code),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for AppError<E> {}
@@ -16,10 +16,10 @@
 // under the License.
 
 pub use self::context::Context;
-pub use self::error::{Error, ErrorKind, Result};
+pub use self::error::{AppError, Error, ErrorKind, Result, APP_ERROR_MAX, APP_ERROR_MIN};
 pub use self::operation::Operation;
 pub use self::parameter::{Param, ParamNone, ParamTmpRef, ParamType, ParamTypes, ParamValue};
-pub use self::session::{ConnectionMethods, Session};
+pub use self::session::{ConnectionMethods, Session, TRACE_BUFFER_DRAIN_COMMAND};
 pub use self::uuid::Uuid;
 pub use self::extension::*;
 pub use optee_teec_macros::{plugin_init, plugin_invoke};
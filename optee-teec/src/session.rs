@@ -20,9 +20,15 @@ use optee_teec_sys as raw;
 use std::ptr;
 use std::marker;
 
-use crate::Param;
+use crate::{Param, ParamNone, ParamTmpRef, ParamType, ParamValue};
 use crate::{Context, Error, Operation, Result, Uuid};
 
+/// The command reserved by the entry points of TAs built with `optee-utee` to drain their
+/// trace buffer, see [fetch_trace_buffer](Session::fetch_trace_buffer).
+pub const TRACE_BUFFER_DRAIN_COMMAND: u32 = 0xFFFF_FF00;
+
+const TRACE_BUFFER_CHUNK_SIZE: usize = 4096;
+
 /// Session login methods.
 #[derive(Copy, Clone)]
 pub enum ConnectionMethods {
@@ -102,6 +108,40 @@ impl<'ctx> Session<'ctx> {
             }
         }
     }
+
+    /// Drains the trace buffer of the TA, which keeps a copy of its trace output when built
+    /// with `optee-utee`. Output overwritten in the TA before it could be fetched is replaced
+    /// with a `[N bytes lost]` line.
+    ///
+    /// The TA refuses the command with `AccessDenied` unless it authorized the client, and
+    /// with `ItemNotFound` if it does not record its trace output.
+    pub fn fetch_trace_buffer(&mut self) -> Result<Vec<u8>> {
+        let mut records = Vec::new();
+        let mut chunk = [0u8; TRACE_BUFFER_CHUNK_SIZE];
+        loop {
+            let p0 = ParamTmpRef::new_output(&mut chunk);
+            let p1 = ParamValue::new(0, 0, ParamType::ValueOutput);
+            let mut operation = Operation::new(0, p0, p1, ParamNone, ParamNone);
+            self.invoke_command(TRACE_BUFFER_DRAIN_COMMAND, &mut operation)?;
+            let (p0, p1, _, _) = operation.parameters();
+            let (len, left, lost) = (p0.updated_size(), p1.a(), p1.b());
+            if lost != 0 {
+                records.extend_from_slice(format!("[{} bytes lost]\n", lost).as_bytes());
+            }
+            records.extend_from_slice(&chunk[..len.min(chunk.len())]);
+            if left == 0 || len == 0 {
+                return Ok(records);
+            }
+        }
+    }
+
+    /// Drains the trace buffer of the TA and prints it to the standard output, see
+    /// [fetch_trace_buffer](Session::fetch_trace_buffer).
+    pub fn print_trace_buffer(&mut self) -> Result<()> {
+        let records = self.fetch_trace_buffer()?;
+        print!("{}", String::from_utf8_lossy(&records));
+        Ok(())
+    }
 }
 
 impl<'ctx> Drop for Session<'ctx> {
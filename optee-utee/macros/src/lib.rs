@@ -305,6 +305,12 @@ pub fn ta_close_session(_args: TokenStream, input: TokenStream) -> TokenStream {
 /// Attribute to declare the entry point of invoking commands. Session context
 /// reference (`&mut T`) can be defined as an optional parameter.
 ///
+/// The command `optee_utee::trace_buffer::DRAIN_COMMAND` is reserved: it drains the trace
+/// buffer and is never passed to the function.
+///
+/// With `error_message = N`, the detail of an error returned by the function, see
+/// `Error::with_detail`, is written to the parameter `N` if it is an output memref.
+///
 /// # Examples
 ///
 /// ``` no_run
@@ -313,12 +319,39 @@ pub fn ta_close_session(_args: TokenStream, input: TokenStream) -> TokenStream {
 ///
 /// #[ta_invoke_command]
 /// fn invoke_command(cmd_id: u32, params: &mut Parameters) -> Result<()> { }
+///
+/// #[ta_invoke_command(error_message = 3)]
+/// fn invoke_command(cmd_id: u32, params: &mut Parameters) -> Result<()> { }
 /// ```
 #[proc_macro_attribute]
-pub fn ta_invoke_command(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn ta_invoke_command(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as syn::AttributeArgs);
     let f = parse_macro_input!(input as syn::ItemFn);
     let ident = &f.ident;
 
+    // `error_message = N` designates the parameter receiving the detail of an error
+    let mut write_detail = quote!();
+    for arg in args.iter() {
+        let index = match arg {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                ident,
+                lit: syn::Lit::Int(index),
+                ..
+            })) if ident == "error_message" && index.value() < 4 => {
+                syn::Index::from(index.value() as usize)
+            }
+            _ => {
+                return syn::parse::Error::new(
+                    arg.span(),
+                    "`#[ta_invoke_command]` only accepts `error_message = N` with `N` in 0..4",
+                )
+                .to_compile_error()
+                .into();
+            }
+        };
+        write_detail = quote!(e._write_detail(&mut parameters.#index););
+    }
+
     // check the function signature
     let valid_signature = f.constness.is_none()
         && match f.vis {
@@ -349,11 +382,20 @@ pub fn ta_invoke_command(_args: TokenStream, input: TokenStream) -> TokenStream
                 params: &mut [optee_utee_sys::TEE_Param; 4],
             ) -> optee_utee_sys::TEE_Result {
                 let mut parameters = Parameters::from_raw(params, param_types);
+                if cmd_id == optee_utee::trace_buffer::DRAIN_COMMAND {
+                    return match optee_utee::trace_buffer::_invoke_drain(&mut parameters) {
+                        Ok(_) => optee_utee_sys::TEE_SUCCESS,
+                        Err(e) => e.raw_code(),
+                    };
+                }
                 match #ident(cmd_id, &mut parameters) {
                     Ok(_) => {
                         optee_utee_sys::TEE_SUCCESS
                     },
-                    Err(e) => e.raw_code()
+                    Err(e) => {
+                        #write_detail
+                        e.raw_code()
+                    }
                 }
             }
 
@@ -387,13 +429,22 @@ pub fn ta_invoke_command(_args: TokenStream, input: TokenStream) -> TokenStream
                         return optee_utee_sys::TEE_ERROR_SECURITY;
                     }
                     let mut parameters = Parameters::from_raw(params, param_types);
+                    if cmd_id == optee_utee::trace_buffer::DRAIN_COMMAND {
+                        return match optee_utee::trace_buffer::_invoke_drain(&mut parameters) {
+                            Ok(_) => optee_utee_sys::TEE_SUCCESS,
+                            Err(e) => e.raw_code(),
+                        };
+                    }
                     let mut b = unsafe {Box::from_raw(sess_ctx as *mut #t)};
                     match #ident(&mut b, cmd_id, &mut parameters) {
                         Ok(_) => {
                             std::mem::forget(b);
                             optee_utee_sys::TEE_SUCCESS
                         },
-                        Err(e) => e.raw_code()
+                        Err(e) => {
+                            #write_detail
+                            e.raw_code()
+                        }
                     }
                 }
 
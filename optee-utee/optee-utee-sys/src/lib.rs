@@ -19,6 +19,7 @@
 
 pub use tee_api::*;
 pub use tee_api_defines::*;
+pub use tee_api_defines_extensions::*;
 pub use tee_api_types::*;
 pub use tee_internal_api_extensions::*;
 pub use tee_isocket::*;
@@ -32,6 +33,7 @@ pub use utee_types::*;
 
 mod tee_api;
 mod tee_api_defines;
+mod tee_api_defines_extensions;
 mod tee_api_types;
 mod tee_internal_api_extensions;
 mod tee_isocket;
@@ -289,6 +289,12 @@ extern "C" {
         hash: *mut c_void,
         hashLen: *mut u32,
     ) -> TEE_Result;
+    // GP 1.3 only, `hashLen` is a `size_t` whatever the API version the TA is built for.
+    pub fn TEE_DigestExtract(
+        operation: TEE_OperationHandle,
+        hash: *mut c_void,
+        hashLen: *mut size_t,
+    ) -> TEE_Result;
 
     // Cryptographic Operations API - Symmetric Cipher Functions
 
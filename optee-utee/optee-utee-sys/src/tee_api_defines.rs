@@ -187,6 +187,10 @@ pub const TEE_ALG_HMAC_SHA256: u32 = 0x30000004;
 pub const TEE_ALG_HMAC_SHA384: u32 = 0x30000005;
 pub const TEE_ALG_HMAC_SHA512: u32 = 0x30000006;
 pub const TEE_ALG_HMAC_SM3: u32 = 0x30000007;
+pub const TEE_ALG_HMAC_SHA3_224: u32 = 0x30000008;
+pub const TEE_ALG_HMAC_SHA3_256: u32 = 0x30000009;
+pub const TEE_ALG_HMAC_SHA3_384: u32 = 0x3000000A;
+pub const TEE_ALG_HMAC_SHA3_512: u32 = 0x3000000B;
 pub const TEE_ALG_ECDSA_P192: u32 = 0x70001041;
 pub const TEE_ALG_ECDSA_P224: u32 = 0x70002041;
 pub const TEE_ALG_ECDSA_P256: u32 = 0x70003041;
@@ -197,8 +201,16 @@ pub const TEE_ALG_ECDH_P224: u32 = 0x80002042;
 pub const TEE_ALG_ECDH_P256: u32 = 0x80003042;
 pub const TEE_ALG_ECDH_P384: u32 = 0x80004042;
 pub const TEE_ALG_ECDH_P521: u32 = 0x80005042;
+pub const TEE_ALG_ED25519: u32 = 0x70006043;
+pub const TEE_ALG_X25519: u32 = 0x80000044;
 pub const TEE_ALG_SM2_PKE: u32 = 0x80000045;
 pub const TEE_ALG_SM3: u32 = 0x50000007;
+pub const TEE_ALG_SHA3_224: u32 = 0x50000008;
+pub const TEE_ALG_SHA3_256: u32 = 0x50000009;
+pub const TEE_ALG_SHA3_384: u32 = 0x5000000A;
+pub const TEE_ALG_SHA3_512: u32 = 0x5000000B;
+pub const TEE_ALG_SHAKE128: u32 = 0x50000101;
+pub const TEE_ALG_SHAKE256: u32 = 0x50000102;
 pub const TEE_ALG_ILLEGAL_VALUE: u32 = 0xEFFFFFFF;
 
 // Object Types
@@ -213,6 +225,10 @@ pub const TEE_TYPE_HMAC_SHA256: u32 = 0xA0000004;
 pub const TEE_TYPE_HMAC_SHA384: u32 = 0xA0000005;
 pub const TEE_TYPE_HMAC_SHA512: u32 = 0xA0000006;
 pub const TEE_TYPE_HMAC_SM3: u32 = 0xA0000007;
+pub const TEE_TYPE_HMAC_SHA3_224: u32 = 0xA0000008;
+pub const TEE_TYPE_HMAC_SHA3_256: u32 = 0xA0000009;
+pub const TEE_TYPE_HMAC_SHA3_384: u32 = 0xA000000A;
+pub const TEE_TYPE_HMAC_SHA3_512: u32 = 0xA000000B;
 pub const TEE_TYPE_RSA_PUBLIC_KEY: u32 = 0xA0000030;
 pub const TEE_TYPE_RSA_KEYPAIR: u32 = 0xA1000030;
 pub const TEE_TYPE_DSA_PUBLIC_KEY: u32 = 0xA0000031;
@@ -222,6 +238,10 @@ pub const TEE_TYPE_ECDSA_PUBLIC_KEY: u32 = 0xA0000041;
 pub const TEE_TYPE_ECDSA_KEYPAIR: u32 = 0xA1000041;
 pub const TEE_TYPE_ECDH_PUBLIC_KEY: u32 = 0xA0000042;
 pub const TEE_TYPE_ECDH_KEYPAIR: u32 = 0xA1000042;
+pub const TEE_TYPE_ED25519_PUBLIC_KEY: u32 = 0xA0000043;
+pub const TEE_TYPE_ED25519_KEYPAIR: u32 = 0xA1000043;
+pub const TEE_TYPE_X25519_PUBLIC_KEY: u32 = 0xA0000044;
+pub const TEE_TYPE_X25519_KEYPAIR: u32 = 0xA1000044;
 pub const TEE_TYPE_SM2_DSA_PUBLIC_KEY: u32 = 0xA0000045;
 pub const TEE_TYPE_SM2_DSA_KEYPAIR: u32 = 0xA1000045;
 pub const TEE_TYPE_SM2_KEP_PUBLIC_KEY: u32 = 0xA0000046;
@@ -266,6 +286,12 @@ pub const TEE_ATTR_SM2_KEP_CONFIRMATION_IN: u32 = 0xD0000746;
 pub const TEE_ATTR_SM2_KEP_CONFIRMATION_OUT: u32 = 0xD0000846;
 pub const TEE_ATTR_ECC_EPHEMERAL_PUBLIC_VALUE_X: u32 = 0xD0000946;
 pub const TEE_ATTR_ECC_EPHEMERAL_PUBLIC_VALUE_Y: u32 = 0xD0000A46;
+pub const TEE_ATTR_EDDSA_PREHASH: u32 = 0xF0000004;
+pub const TEE_ATTR_EDDSA_CTX: u32 = 0xD0000643;
+pub const TEE_ATTR_ED25519_PUBLIC_VALUE: u32 = 0xD0000743;
+pub const TEE_ATTR_ED25519_PRIVATE_VALUE: u32 = 0xC0000843;
+pub const TEE_ATTR_X25519_PUBLIC_VALUE: u32 = 0xD0000944;
+pub const TEE_ATTR_X25519_PRIVATE_VALUE: u32 = 0xC0000A44;
 pub const TEE_ATTR_FLAG_PUBLIC: u32 = 1 << 28;
 pub const TEE_ATTR_FLAG_VALUE: u32 = 1 << 29;
 // Deprecated, but kept for backwards compatibility
@@ -0,0 +1,57 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+// OP-TEE extension algorithms, object types and attributes, see
+// lib/libutee/include/tee_api_defines_extensions.h in optee_os.
+
+// HKDF
+pub const TEE_ALG_HKDF_MD5_DERIVE_KEY: u32 = 0x800010C0;
+pub const TEE_ALG_HKDF_SHA1_DERIVE_KEY: u32 = 0x800020C0;
+pub const TEE_ALG_HKDF_SHA224_DERIVE_KEY: u32 = 0x800030C0;
+pub const TEE_ALG_HKDF_SHA256_DERIVE_KEY: u32 = 0x800040C0;
+pub const TEE_ALG_HKDF_SHA384_DERIVE_KEY: u32 = 0x800050C0;
+pub const TEE_ALG_HKDF_SHA512_DERIVE_KEY: u32 = 0x800060C0;
+
+pub const TEE_TYPE_HKDF_IKM: u32 = 0xA10000C0;
+
+pub const TEE_ATTR_HKDF_IKM: u32 = 0xC00001C0;
+pub const TEE_ATTR_HKDF_SALT: u32 = 0xD00002C0;
+pub const TEE_ATTR_HKDF_INFO: u32 = 0xD00003C0;
+pub const TEE_ATTR_HKDF_OKM_LENGTH: u32 = 0xF00004C0;
+
+// Concatenation Key Derivation Function (Concat KDF), NIST SP 800-56A section 5.8.1
+pub const TEE_ALG_CONCAT_KDF_SHA1_DERIVE_KEY: u32 = 0x800020C1;
+pub const TEE_ALG_CONCAT_KDF_SHA224_DERIVE_KEY: u32 = 0x800030C1;
+pub const TEE_ALG_CONCAT_KDF_SHA256_DERIVE_KEY: u32 = 0x800040C1;
+pub const TEE_ALG_CONCAT_KDF_SHA384_DERIVE_KEY: u32 = 0x800050C1;
+pub const TEE_ALG_CONCAT_KDF_SHA512_DERIVE_KEY: u32 = 0x800060C1;
+
+pub const TEE_TYPE_CONCAT_KDF_Z: u32 = 0xA10000C1;
+
+pub const TEE_ATTR_CONCAT_KDF_Z: u32 = 0xC00001C1;
+pub const TEE_ATTR_CONCAT_KDF_OTHER_INFO: u32 = 0xD00002C1;
+pub const TEE_ATTR_CONCAT_KDF_DKM_LENGTH: u32 = 0xF00003C1;
+
+// PKCS #5 v2.0 Key Derivation Function 2 (PBKDF2)
+pub const TEE_ALG_PBKDF2_HMAC_SHA1_DERIVE_KEY: u32 = 0x800020C2;
+
+pub const TEE_TYPE_PBKDF2_PASSWORD: u32 = 0xA10000C2;
+
+pub const TEE_ATTR_PBKDF2_PASSWORD: u32 = 0xC00001C2;
+pub const TEE_ATTR_PBKDF2_SALT: u32 = 0xD00002C2;
+pub const TEE_ATTR_PBKDF2_ITERATION_COUNT: u32 = 0xF00003C2;
+pub const TEE_ATTR_PBKDF2_DKM_LENGTH: u32 = 0xF00004C2;
@@ -17,6 +17,13 @@
 
 use libc::*;
 
+pub const TRACE_MIN: c_int = 1;
+pub const TRACE_ERROR: c_int = TRACE_MIN;
+pub const TRACE_INFO: c_int = 2;
+pub const TRACE_DEBUG: c_int = 3;
+pub const TRACE_FLOW: c_int = 4;
+pub const TRACE_MAX: c_int = TRACE_FLOW;
+
 extern "C" {
     pub fn trace_ext_puts(str: *const c_char);
     pub fn trace_ext_get_thread_id() -> c_int;
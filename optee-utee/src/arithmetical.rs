@@ -15,14 +15,16 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::{Error, Result};
+use crate::{Error, ErrorKind, Result};
 use optee_utee_sys as raw;
-use std::{cmp::max, fmt};
+use std::cmp::{max, Ordering};
+use std::{fmt, mem, ops, str::FromStr};
 
 pub type BigIntUnit = u32;
 pub type BigIntFMMUnit = u32;
 pub type BigIntFMMContextUnit = u32;
 
+#[derive(Clone)]
 pub struct BigInt(Vec<BigIntUnit>);
 
 impl BigInt {
@@ -144,12 +146,10 @@ impl BigInt {
         res
     }
 
-    // document defines wrong size for result quotient
+    // document defines wrong size for result quotient, the magnitude of the quotient is at most
+    // one bit longer than the difference of the operand lengths
     pub fn divide(op1: &Self, op2: &Self) -> (Self, Self) {
-        let q_bits = match op1.compare_big_int(op2) {
-            d if d >= 0 => max(1, Self::get_bit_count(op1) - Self::get_bit_count(op2)),
-            _ => 0,
-        };
+        let q_bits = Self::get_bit_count(op1).saturating_sub(Self::get_bit_count(op2)) + 1;
         let r_bits = Self::get_bit_count(op2);
         let mut quotient = Self::new(q_bits);
         let mut remainder = Self::new(r_bits);
@@ -226,22 +226,41 @@ impl BigInt {
         unsafe { raw::TEE_BigIntRelativePrime(op1.data_ptr(), op2.data_ptr()) }
     }
 
-    /* pub fn compute_extended_gcd(op1: &Self, op2: &Self) -> (Self, Self, Self)
-     * This function is implemented in OP-TEE, while the output size needs to be calculated based
-     * on the missing function TEE_BigIntAbs, so we do not port it yet.*/
+    /// Compute the greatest common divisor of `op1` and `op2`, together with the coefficients
+    /// `u` and `v` such that `u * op1 + v * op2 == gcd`.
+    ///
+    /// Returns `(gcd, u, v)`.
+    pub fn extended_gcd(op1: &Self, op2: &Self) -> (Self, Self, Self) {
+        // |gcd|, |u| and |v| are bounded by the larger operand, which avoids TEE_BigIntAbs.
+        let bits = max(Self::get_bit_count(op1), Self::get_bit_count(op2)) + 1;
+        let mut gcd = Self::new(bits);
+        let mut u = Self::new(bits);
+        let mut v = Self::new(bits);
+        unsafe {
+            raw::TEE_BigIntComputeExtendedGcd(
+                gcd.0.as_mut_ptr(),
+                u.0.as_mut_ptr(),
+                v.0.as_mut_ptr(),
+                op1.data_ptr(),
+                op2.data_ptr(),
+            )
+        };
+        (gcd, u, v)
+    }
 
     pub fn is_probable_prime(&self, confidence_level: u32) -> i32 {
         unsafe { raw::TEE_BigIntIsProbablePrime(self.data_ptr(), confidence_level) }
     }
 
+    //Has to be initialized with the size of the modulus first
     pub fn convert_from_big_int_fmm(
         &mut self,
         src: &BigIntFMM,
         n: &BigInt,
-        context: BigIntFMMContext,
+        context: &BigIntFMMContext,
     ) {
         unsafe {
-            raw::TEE_BigIntConvertToFMM(
+            raw::TEE_BigIntConvertFromFMM(
                 self.0.as_mut_ptr(),
                 src.data_ptr(),
                 n.data_ptr(),
@@ -251,17 +270,241 @@ impl BigInt {
     }
 }
 
+impl BigInt {
+    /// Parse a number written in base `radix` with an optional leading `-`.
+    ///
+    /// # Errors
+    ///
+    /// `BadFormat`: `src` holds no digit or a character which is not a digit of `radix`.
+    /// `BadParameters`: `radix` is neither 10 nor 16.
+    pub fn from_str_radix(src: &str, radix: u32) -> Result<Self> {
+        match src.strip_prefix('-') {
+            Some(digits) => Self::parse(digits, radix, true),
+            None => Self::parse(src, radix, false),
+        }
+    }
+
+    fn parse(digits: &str, radix: u32, negative: bool) -> Result<Self> {
+        let buffer = parse_magnitude(digits, radix)?;
+        let mut res = Self::new(buffer.len() as u32 * 8);
+        res.convert_from_octet_string(&buffer, if negative { -1 } else { 0 })?;
+        Ok(res)
+    }
+
+    // digits of the absolute value in base 10 or 16, lower case
+    fn magnitude_digits(&self, radix: u32) -> Result<String> {
+        Ok(format_magnitude(&self.convert_to_octet_string()?, radix))
+    }
+
+    fn fmt_radix(
+        &self,
+        f: &mut fmt::Formatter,
+        radix: u32,
+        prefix: &str,
+        upper: bool,
+    ) -> fmt::Result {
+        let mut digits = self.magnitude_digits(radix).map_err(|_| fmt::Error)?;
+        if upper {
+            digits.make_ascii_uppercase();
+        }
+        f.pad_integral(self.compare_s32(0) >= 0, prefix, &digits)
+    }
+}
+
+// Parse digits in base 10 or 16 into the big-endian bytes of the magnitude, a whole number of
+// 32-bit units long.
+fn parse_magnitude(digits: &str, radix: u32) -> Result<Vec<u8>> {
+    if radix != 10 && radix != 16 {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    if digits.is_empty() {
+        return Err(Error::new(ErrorKind::BadFormat));
+    }
+    // little-endian units of the magnitude
+    let mut units: Vec<u32> = Vec::new();
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix).ok_or(Error::new(ErrorKind::BadFormat))? as u64;
+        for unit in units.iter_mut() {
+            let v = *unit as u64 * radix as u64 + carry;
+            *unit = v as u32;
+            carry = v >> 32;
+        }
+        if carry != 0 {
+            units.push(carry as u32);
+        }
+    }
+    Ok(units.iter().rev().flat_map(|u| u.to_be_bytes()).collect())
+}
+
+// Format the big-endian bytes of a magnitude in base 10 or 16, lower case.
+fn format_magnitude(buffer: &[u8], radix: u32) -> String {
+    let digits = if radix == 16 {
+        buffer
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    } else {
+        // big-endian units, divided by 10^9 until nothing is left
+        let mut units: Vec<u32> = buffer
+            .rchunks(4)
+            .rev()
+            .map(|c| c.iter().fold(0, |acc, b| acc << 8 | *b as u32))
+            .collect();
+        let mut chunks = Vec::new();
+        while units.iter().any(|u| *u != 0) {
+            let mut rem = 0u64;
+            for unit in units.iter_mut() {
+                let v = rem << 32 | *unit as u64;
+                *unit = (v / 1_000_000_000) as u32;
+                rem = v % 1_000_000_000;
+            }
+            chunks.push(rem as u32);
+        }
+        chunks
+            .iter()
+            .rev()
+            .map(|c| format!("{:09}", c))
+            .collect::<String>()
+    };
+    match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Parse a decimal number, or a hexadecimal one when the digits are prefixed with `0x`, e.g.
+/// `"-0x1f"`.
+impl FromStr for BigInt {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Self> {
+        let (negative, digits) = match src.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, src),
+        };
+        match digits.strip_prefix("0x") {
+            Some(hex) => Self::parse(hex, 16, negative),
+            None => Self::parse(digits, 10, negative),
+        }
+    }
+}
+
 impl fmt::Display for BigInt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:x?}", self.0)
+        self.fmt_radix(f, 10, "", false)
     }
 }
 
-pub struct BigIntFMMContext(Vec<BigIntFMMContextUnit>);
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_radix(f, 10, "", false)
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_radix(f, 16, "0x", false)
+    }
+}
+
+impl fmt::UpperHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_radix(f, 16, "0x", true)
+    }
+}
+
+impl PartialEq for BigInt {
+    fn eq(&self, other: &Self) -> bool {
+        self.compare_big_int(other) == 0
+    }
+}
+
+impl Eq for BigInt {}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare_big_int(other).cmp(&0)
+    }
+}
+
+// Implement the operator for references and forward the owned variant to it.
+macro_rules! big_int_binop {
+    ($trait:ident, $method:ident, $func:expr) => {
+        impl<'a, 'b> ops::$trait<&'b BigInt> for &'a BigInt {
+            type Output = BigInt;
+
+            fn $method(self, rhs: &'b BigInt) -> BigInt {
+                $func(self, rhs)
+            }
+        }
+
+        impl ops::$trait for BigInt {
+            type Output = BigInt;
+
+            fn $method(self, rhs: BigInt) -> BigInt {
+                $func(&self, &rhs)
+            }
+        }
+    };
+}
+
+big_int_binop!(Add, add, BigInt::add);
+big_int_binop!(Sub, sub, BigInt::sub);
+big_int_binop!(Mul, mul, BigInt::multiply);
+// Like the primitive integers, the quotient is rounded toward zero and the remainder takes the
+// sign of the dividend. Dividing by zero panics in the TEE.
+big_int_binop!(Div, div, |op1, op2| BigInt::divide(op1, op2).0);
+big_int_binop!(Rem, rem, |op1, op2| BigInt::divide(op1, op2).1);
+
+impl ops::Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::neg(self)
+    }
+}
+
+impl ops::Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::neg(&self)
+    }
+}
+
+impl ops::Shr<usize> for &BigInt {
+    type Output = BigInt;
+
+    fn shr(self, bits: usize) -> BigInt {
+        let res_bits = (self.get_bit_count() as usize).saturating_sub(bits);
+        let mut res = BigInt::new(res_bits as u32);
+        res.shift_right(self, bits);
+        res
+    }
+}
+
+impl ops::Shr<usize> for BigInt {
+    type Output = BigInt;
+
+    fn shr(self, bits: usize) -> BigInt {
+        &self >> bits
+    }
+}
+
+pub struct BigIntFMMContext {
+    data: Vec<BigIntFMMContextUnit>,
+    modulus: BigInt,
+}
 
 impl BigIntFMMContext {
     pub fn data_ptr(&self) -> *const u32 {
-        self.0.as_ptr()
+        self.data.as_ptr()
     }
 
     fn size_in_u32(size: u32) -> u32 {
@@ -275,7 +518,56 @@ impl BigIntFMMContext {
         unsafe {
             raw::TEE_BigIntInitFMMContext(tmp_vec.as_mut_ptr(), size as u32, modulus.data_ptr())
         };
-        Ok(Self(tmp_vec))
+        Ok(Self {
+            data: tmp_vec,
+            modulus,
+        })
+    }
+
+    /// Return the modulus the context was initialized with.
+    pub fn modulus(&self) -> &BigInt {
+        &self.modulus
+    }
+
+    /// Compute `base ^ exp mod n` with fast modular multiplications, `n` being the modulus of
+    /// the context.
+    ///
+    /// The running time depends on the bits of `exp`, so it should not be used with a secret
+    /// exponent.
+    ///
+    /// # Errors
+    ///
+    /// `BadParameters`: `exp` is negative.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let n: BigInt = "0xc5a1f3e7".parse()?;
+    /// let context = BigIntFMMContext::new(n.get_bit_count(), n)?;
+    /// let res = context.mod_exp(&"12345".parse()?, &"65537".parse()?)?;
+    /// ```
+    pub fn mod_exp(&self, base: &BigInt, exp: &BigInt) -> Result<BigInt> {
+        if exp.compare_s32(0) < 0 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        let n = &self.modulus;
+        let bits = n.get_bit_count();
+        let mut one = BigInt::new(1);
+        one.convert_from_s32(1);
+        let mut acc = BigIntFMM::new(bits);
+        acc.convert_from_big_int(&BigInt::module(&one, n), n, self);
+        let mut factor = BigIntFMM::new(bits);
+        factor.convert_from_big_int(&BigInt::module(base, n), n, self);
+        let mut square = BigIntFMM::new(bits);
+        for i in (0..exp.get_bit_count()).rev() {
+            square.compute_fmm(&acc, &acc, n, self);
+            if exp.get_bit(i) {
+                acc.compute_fmm(&square, &factor, n, self);
+            } else {
+                mem::swap(&mut acc, &mut square);
+            }
+        }
+        Ok(acc.convert_to_big_int(n, self))
     }
 }
 
@@ -298,7 +590,7 @@ impl BigIntFMM {
     }
 
     //Has to be initialized first
-    pub fn convert_from_big_int(&mut self, src: &BigInt, n: &BigInt, context: BigIntFMMContext) {
+    pub fn convert_from_big_int(&mut self, src: &BigInt, n: &BigInt, context: &BigIntFMMContext) {
         unsafe {
             raw::TEE_BigIntConvertToFMM(
                 self.0.as_mut_ptr(),
@@ -309,13 +601,22 @@ impl BigIntFMM {
         };
     }
 
+    /// Convert back to a `BigInt` in the range `[0, n)`.
+    pub fn convert_to_big_int(&self, n: &BigInt, context: &BigIntFMMContext) -> BigInt {
+        // TEE_BigIntFMMConvertToBigInt is not implemented by OP-TEE, the conversion goes through
+        // TEE_BigIntConvertFromFMM which does the same.
+        let mut res = BigInt::new(n.get_bit_count());
+        res.convert_from_big_int_fmm(self, n, context);
+        res
+    }
+
     //Has to be initialized first
     pub fn compute_fmm(
         &mut self,
         op1: &BigIntFMM,
         op2: &BigIntFMM,
         n: &BigInt,
-        context: BigIntFMMContext,
+        context: &BigIntFMMContext,
     ) {
         unsafe {
             raw::TEE_BigIntComputeFMM(
@@ -333,3 +634,74 @@ impl BigIntFMM {
 //TEE_BigIntAssign
 //TEE_BigIntAbs
 //TEE_BigIntExpMod
+//TEE_BigIntFMMConvertToBigInt
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digits: &str) -> Vec<u8> {
+        parse_magnitude(digits, 16).unwrap()
+    }
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!(parse_magnitude("0", 10).unwrap(), Vec::<u8>::new());
+        assert_eq!(parse_magnitude("4294967295", 10).unwrap(), hex("ffffffff"));
+        // carries into a second unit
+        assert_eq!(parse_magnitude("4294967296", 10).unwrap(), hex("100000000"));
+        assert_eq!(
+            parse_magnitude("340282366920938463463374607431768211455", 10).unwrap(),
+            vec![0xff; 16]
+        );
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(hex("1f"), vec![0, 0, 0, 0x1f]);
+        assert_eq!(hex("0001f"), vec![0, 0, 0, 0x1f]);
+        assert_eq!(
+            hex("AbCdEf0123"),
+            vec![0, 0, 0, 0xab, 0xcd, 0xef, 0x01, 0x23]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let kind = |digits, radix| parse_magnitude(digits, radix).unwrap_err().kind();
+        assert_eq!(kind("", 10), ErrorKind::BadFormat);
+        assert_eq!(kind("12a", 10), ErrorKind::BadFormat);
+        assert_eq!(kind("-1", 10), ErrorKind::BadFormat);
+        assert_eq!(kind("0x1", 16), ErrorKind::BadFormat);
+        assert_eq!(kind("101", 2), ErrorKind::BadParameters);
+    }
+
+    #[test]
+    fn format_decimal() {
+        assert_eq!(format_magnitude(&[], 10), "0");
+        assert_eq!(format_magnitude(&[0, 0, 0, 0], 10), "0");
+        assert_eq!(
+            format_magnitude(&[0x3b, 0x9a, 0xca, 0x00], 10),
+            "1000000000"
+        );
+        assert_eq!(format_magnitude(&[0x01, 0, 0, 0, 0], 10), "4294967296");
+        assert_eq!(
+            format_magnitude(&[0xff; 16], 10),
+            "340282366920938463463374607431768211455"
+        );
+    }
+
+    #[test]
+    fn format_hex() {
+        assert_eq!(format_magnitude(&[0, 0, 0x01, 0xf0], 16), "1f0");
+        assert_eq!(format_magnitude(&[0, 0], 16), "0");
+    }
+
+    #[test]
+    fn round_trip() {
+        for digits in &["1", "999999999", "1000000000", "18446744073709551617"] {
+            let buffer = parse_magnitude(digits, 10).unwrap();
+            assert_eq!(format_magnitude(&buffer, 10), *digits);
+        }
+    }
+}
@@ -15,11 +15,15 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::{Attribute, Error, ObjHandle, Result, TransientObject};
+use crate::{
+    Attribute, AttributeId, AttributeMemref, AttributeValue, Error, ErrorKind, HandleFlag,
+    ObjHandle, Result, Secret, TransientObject, TransientObjectType, UsageFlag,
+};
 use optee_utee_sys as raw;
-use std::{mem, ptr};
+use std::{marker::PhantomData, mem, ptr};
 
 /// Specify one of the available cryptographic operations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum OperationMode {
     /// Encryption mode
@@ -40,6 +44,23 @@ pub enum OperationMode {
     IllegalValue = 0x7fffffff,
 }
 
+impl OperationMode {
+    /// Return the mode identified by `raw`, or `None` if it is not a known mode.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(OperationMode::Encrypt),
+            1 => Some(OperationMode::Decrypt),
+            2 => Some(OperationMode::Sign),
+            3 => Some(OperationMode::Verify),
+            4 => Some(OperationMode::Mac),
+            5 => Some(OperationMode::Digest),
+            6 => Some(OperationMode::Derive),
+            0x7fffffff => Some(OperationMode::IllegalValue),
+            _ => None,
+        }
+    }
+}
+
 /// Represent the information about a crypto information.
 pub struct OperationInfo {
     raw: raw::TEE_OperationInfo,
@@ -78,10 +99,47 @@ impl OperationInfo {
         self.raw.keySize
     }
 
-    /// Return the `maxDataSize` field of the raw structure `TEE_OperationInfo`.
+    /// Return the `maxKeySize` field of the raw structure `TEE_OperationInfo`.
     pub fn max_key_size(&self) -> u32 {
         self.raw.maxKeySize
     }
+
+    /// Return the `algorithm` field of the raw structure `TEE_OperationInfo`.
+    pub fn algorithm(&self) -> u32 {
+        self.raw.algorithm
+    }
+
+    /// Return the `algorithm` field of the raw structure `TEE_OperationInfo` as an
+    /// [AlgorithmId](AlgorithmId), or `None` for an algorithm unknown to this crate.
+    pub fn algorithm_id(&self) -> Option<AlgorithmId> {
+        AlgorithmId::from_raw(self.raw.algorithm)
+    }
+
+    /// Return the `operationClass` field of the raw structure `TEE_OperationInfo`.
+    pub fn operation_class(&self) -> Option<OperationConstant> {
+        OperationConstant::from_raw(self.raw.operationClass)
+    }
+
+    /// Return the `mode` field of the raw structure `TEE_OperationInfo` as an
+    /// [OperationMode](OperationMode).
+    pub fn operation_mode(&self) -> Option<OperationMode> {
+        OperationMode::from_raw(self.raw.mode)
+    }
+
+    /// Return the `digestLength` field of the raw structure `TEE_OperationInfo`.
+    pub fn digest_length(&self) -> u32 {
+        self.raw.digestLength
+    }
+
+    /// Return the `requiredKeyUsage` field of the raw structure `TEE_OperationInfo`.
+    pub fn required_key_usage(&self) -> UsageFlag {
+        UsageFlag::from_bits_truncate(self.raw.requiredKeyUsage)
+    }
+
+    /// Return the `handleState` field of the raw structure `TEE_OperationInfo`.
+    pub fn handle_state(&self) -> HandleFlag {
+        HandleFlag::from_bits_truncate(self.raw.handleState)
+    }
 }
 
 /// Every operation of [AE](AE), [Asymmetric](Asymmetric), [Cipher](Cipher),
@@ -95,6 +153,7 @@ pub enum OperationStates {
 }
 
 /// Define the supported crypto operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OperationConstant {
     /// [Cipher](Cipher)
     Cipher = 1,
@@ -112,13 +171,36 @@ pub enum OperationConstant {
     KeyDerivation = 8,
 }
 
+impl OperationConstant {
+    /// Return the operation class identified by `raw`, or `None` if it is not a known class.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            1 => Some(OperationConstant::Cipher),
+            3 => Some(OperationConstant::Mac),
+            4 => Some(OperationConstant::Ae),
+            5 => Some(OperationConstant::Digest),
+            6 => Some(OperationConstant::AsymmetricCipher),
+            7 => Some(OperationConstant::AsymmetricSignature),
+            8 => Some(OperationConstant::KeyDerivation),
+            _ => None,
+        }
+    }
+}
+
 /// Represent the information about a crypto information which uses multiple keys.
-pub struct OperationInfoMultiple {
+///
+/// It borrows the buffer passed to `info_multiple`, which holds the key entries.
+pub struct OperationInfoMultiple<'a> {
     raw: *mut raw::TEE_OperationInfoMultiple,
     size: usize,
+    _marker: PhantomData<&'a mut [u8]>,
 }
 
-impl OperationInfoMultiple {
+// `keyInformation` is a flexible array member in C, the entries start right after the eight
+// `u32` fields of the header instead of behind a pointer.
+const INFO_MULTIPLE_HEADER_SIZE: usize = 8 * mem::size_of::<u32>();
+
+impl<'a> OperationInfoMultiple<'a> {
     /// Return the `OperationInfoMultiple` struct based on the raw struct `TEE_OperationInfo`.
     ///
     /// The raw structure contains following fields:
@@ -139,8 +221,28 @@ impl OperationInfoMultiple {
     /// For each element:
     /// 9.1) `keySize`: If a key is programmed in the operation, the actual size of this key, otherwise 0.
     /// 9.2) `requiredKeyUsage`: A bit vector that describes the necessary bits in the object usage for `set_key` or `set_key_2` to succeed without panicking.
-    pub fn from_raw(raw: *mut raw::TEE_OperationInfoMultiple, size: usize) -> Self {
-        Self { raw, size }
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to `size` bytes, filled by `TEE_GetOperationInfoMultiple` and valid for
+    /// reads and writes during the lifetime `'a`. The pointer does not need to be aligned.
+    pub unsafe fn from_raw(raw: *mut raw::TEE_OperationInfoMultiple, size: usize) -> Self {
+        Self {
+            raw,
+            size,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Return the size of the buffer to pass to `info_multiple` for an operation with
+    /// `number_of_keys` keys.
+    pub const fn buffer_size(number_of_keys: usize) -> usize {
+        INFO_MULTIPLE_HEADER_SIZE + number_of_keys * mem::size_of::<raw::TEE_OperationInfoKey>()
+    }
+
+    // The buffer is not necessarily aligned for `u32`.
+    fn field(&self, index: usize) -> u32 {
+        unsafe { ptr::read_unaligned((self.raw as *const u32).add(index)) }
     }
 
     /// Return the raw struct `TEE_OperationInfoMultiple`.
@@ -152,8 +254,120 @@ impl OperationInfoMultiple {
     pub fn size(&self) -> usize {
         self.size
     }
+
+    /// Return the `algorithm` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn algorithm(&self) -> u32 {
+        self.field(0)
+    }
+
+    /// Return the `algorithm` field as an [AlgorithmId](AlgorithmId), or `None` for an
+    /// algorithm unknown to this crate.
+    pub fn algorithm_id(&self) -> Option<AlgorithmId> {
+        AlgorithmId::from_raw(self.algorithm())
+    }
+
+    /// Return the `operationClass` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn operation_class(&self) -> Option<OperationConstant> {
+        OperationConstant::from_raw(self.field(1))
+    }
+
+    /// Return the `mode` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn operation_mode(&self) -> Option<OperationMode> {
+        OperationMode::from_raw(self.field(2))
+    }
+
+    /// Return the `digestLength` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn digest_length(&self) -> u32 {
+        self.field(3)
+    }
+
+    /// Return the `maxKeySize` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn max_key_size(&self) -> u32 {
+        self.field(4)
+    }
+
+    /// Return the `handleState` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn handle_state(&self) -> HandleFlag {
+        HandleFlag::from_bits_truncate(self.field(5))
+    }
+
+    /// Return the `operationState` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn operation_state(&self) -> OperationStates {
+        match self.field(6) {
+            0 => OperationStates::Initial,
+            _ => OperationStates::Active,
+        }
+    }
+
+    /// Return the `numberOfKeys` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn number_of_keys(&self) -> usize {
+        self.field(7) as usize
+    }
+
+    /// Return an iterator over the `keyInformation` entries, one per key of the operation.
+    ///
+    /// The iterator stops early if the buffer holds fewer entries than `numberOfKeys`.
+    pub fn keys(&self) -> OperationInfoKeys<'_> {
+        let stored = self.size.saturating_sub(INFO_MULTIPLE_HEADER_SIZE)
+            / mem::size_of::<raw::TEE_OperationInfoKey>();
+        OperationInfoKeys {
+            info: self,
+            index: 0,
+            len: stored.min(self.number_of_keys()),
+        }
+    }
+}
+
+/// The details of one key of an operation, as reported in `TEE_OperationInfoMultiple`.
+#[derive(Clone, Copy, Debug)]
+pub struct OperationInfoKey {
+    key_size: u32,
+    required_key_usage: u32,
+}
+
+impl OperationInfoKey {
+    /// Return the `keySize` field of the raw structure `TEE_OperationInfoKey`, 0 if no key is
+    /// programmed in this slot.
+    pub fn key_size(&self) -> u32 {
+        self.key_size
+    }
+
+    /// Return the `requiredKeyUsage` field of the raw structure `TEE_OperationInfoKey`.
+    pub fn required_key_usage(&self) -> UsageFlag {
+        UsageFlag::from_bits_truncate(self.required_key_usage)
+    }
 }
 
+/// An iterator over the key entries of an [OperationInfoMultiple](OperationInfoMultiple).
+pub struct OperationInfoKeys<'a> {
+    info: &'a OperationInfoMultiple<'a>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a> Iterator for OperationInfoKeys<'a> {
+    type Item = OperationInfoKey;
+
+    fn next(&mut self) -> Option<OperationInfoKey> {
+        if self.index == self.len {
+            return None;
+        }
+        let first = INFO_MULTIPLE_HEADER_SIZE / mem::size_of::<u32>() + 2 * self.index;
+        self.index += 1;
+        Some(OperationInfoKey {
+            key_size: self.info.field(first),
+            required_key_usage: self.info.field(first + 1),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for OperationInfoKeys<'a> {}
+
 /// An opaque reference that identifies a particular cryptographic operation.
 pub struct OperationHandle {
     raw: *mut raw::TEE_OperationHandle,
@@ -193,15 +407,19 @@ impl OperationHandle {
         OperationInfo::from_raw(raw_info)
     }
 
-    fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
-        let mut tmp_size: u32 = 0;
+    fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
+        let mut tmp_size: u32 = info_buf.len() as u32;
         match unsafe {
-            raw::TEE_GetOperationInfoMultiple(self.handle(), info_buf.as_ptr() as _, &mut tmp_size)
+            raw::TEE_GetOperationInfoMultiple(
+                self.handle(),
+                info_buf.as_mut_ptr() as _,
+                &mut tmp_size,
+            )
         } {
-            raw::TEE_SUCCESS => Ok(OperationInfoMultiple::from_raw(
-                info_buf.as_ptr() as _,
-                tmp_size as usize,
-            )),
+            // The TEE filled `tmp_size` bytes of `info_buf`, which is borrowed for `'a`.
+            raw::TEE_SUCCESS => Ok(unsafe {
+                OperationInfoMultiple::from_raw(info_buf.as_mut_ptr() as _, tmp_size as usize)
+            }),
             code => Err(Error::from_raw_error(code)),
         }
     }
@@ -285,7 +503,9 @@ impl Digest {
     ///
     /// 1) `chunk`: Last chunk of data to be hashed.
     /// 2) `hash`: Output buffer filled with the message hash. This buffer should be large enough to
-    ///    hold the hash message. The real used size is returned by this function.
+    ///    hold the hash message. The real used size is returned by this function. For the
+    ///    extendable-output algorithms [Shake128](AlgorithmId::Shake128) and
+    ///    [Shake256](AlgorithmId::Shake256) the whole buffer is filled.
     ///
     /// # Example
     ///
@@ -335,6 +555,50 @@ impl Digest {
         }
     }
 
+    /// Extract output from an extendable-output operation
+    /// ([Shake128](AlgorithmId::Shake128) or [Shake256](AlgorithmId::Shake256)). The first
+    /// call completes the absorbing phase, so no more data can be passed to
+    /// [update](Digest::update) afterwards. Subsequent calls continue the output stream where
+    /// the previous call stopped, so the output can be read in pieces of any size. Use
+    /// [reset](Digest::reset) to start a new message.
+    ///
+    /// It calls `TEE_DigestExtract` of the GlobalPlatform API 1.3, which libutee exports from
+    /// OP-TEE 4.1.0 on: a TA using this function does not link against an older OP-TEE.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `out`: Output buffer entirely filled with the next bytes of the output stream.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let operation = Digest::allocate(AlgorithmId::Shake256)?;
+    /// operation.update(b"seed");
+    /// let mut first = [0u8; 32];
+    /// let mut second = [0u8; 64];
+    /// operation.squeeze(&mut first)?;
+    /// operation.squeeze(&mut second)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If the implementation does not support extracting output.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the operation is not allocated with an extendable-output algorithm.
+    /// 2) Hardware or cryptographic algorithm failure.
+    /// 3) If the Implementation detects any other error.
+    pub fn squeeze(&self, out: &mut [u8]) -> Result<()> {
+        let mut out_size: usize = out.len();
+        match unsafe {
+            raw::TEE_DigestExtract(self.handle(), out.as_mut_ptr() as _, &mut out_size)
+        } {
+            raw::TEE_SUCCESS => Ok(()),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
     /// Create a Digest operation without any specific algorithm or other data.
     pub fn null() -> Self {
         Self(OperationHandle::null())
@@ -405,8 +669,8 @@ impl Digest {
     /// # Parameters
     ///
     /// 1) `info_buf`: The buffer is supposed to save multiple keys, and its size should be large enough before passed in.
-    /// The number of keys about this operation can be calculated as: OperationInfoMultiple::size -
-    /// size_of([OperationInfoMultiple](OperationInfoMultiple)) / size_of ( raw::TEE_OperationInfoKey)+1.
+    /// [OperationInfoMultiple::buffer_size](OperationInfoMultiple::buffer_size) returns the size
+    /// needed for a given number of keys.
     ///
     /// # Example
     ///
@@ -414,10 +678,12 @@ impl Digest {
     /// match Digest::allocate(AlgorithmId::Md5, 128) {
     ///     Ok(operation) =>
     ///     {
-    ///         let mut buffer = [0u32, 12];
+    ///         let mut buffer = [0u8; OperationInfoMultiple::buffer_size(2)];
     ///         match operation.info_multiple(&mut buffer) {
     ///             Ok(info_multiple) => {
-    ///                 // ...
+    ///                 for key in info_multiple.keys() {
+    ///                     trace_println!("{} bits, usage {:?}", key.key_size(), key.required_key_usage());
+    ///                 }
     ///                 Ok(())
     ///             }
     ///             Err(e) => Err(e),
@@ -438,7 +704,7 @@ impl Digest {
     /// 2) If the Implementation detects any other error.
     // Here the multiple info total size is not sure
     // Passed in array is supposed to provide enough size for this struct
-    pub fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
+    pub fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
         self.0.info_multiple(info_buf)
     }
 
@@ -659,10 +925,15 @@ impl Cipher {
     }
 
     /// Function usage is similar to [Digest::info_multiple](Digest::info_multiple).
-    pub fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
+    pub fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
         self.0.info_multiple(info_buf)
     }
 
+    /// Function usage is similar to [Digest::reset](Digest::reset).
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
     /// Program the key of Digest operation. That ids, it associates the operation with a key.
     ///
     /// # Parameters
@@ -902,7 +1173,7 @@ impl Mac {
     }
 
     /// Function usage is similar to [Digest::info_multiple](Digest::info_multiple).
-    pub fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
+    pub fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
         self.0.info_multiple(info_buf)
     }
 
@@ -1185,7 +1456,7 @@ impl AE {
     }
 
     /// Function usage is similar to [Digest::info_multiple](Digest::info_multiple).
-    pub fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
+    pub fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
         self.0.info_multiple(info_buf)
     }
 
@@ -1334,13 +1605,64 @@ impl Asymmetric {
         }
     }
 
+    /// Decrypt a message into a [Secret](../secret/struct.Secret.html) buffer, which is wiped
+    /// when dropped. The parameters, errors and panics are the same as
+    /// [decrypt](Asymmetric::decrypt).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let operation =
+    ///     Asymmetric::allocate(AlgorithmId::RsaesPkcs1OAepMgf1Sha256, OperationMode::Decrypt, 2048)?;
+    /// operation.set_key(&key)?;
+    /// let wrapped_key = operation.decrypt_secret(&[], &ciphertext)?;
+    /// ```
+    pub fn decrypt_secret(&self, params: &[Attribute], src: &[u8]) -> Result<Secret<Vec<u8>>> {
+        let p: Vec<raw::TEE_Attribute> = params.iter().map(|p| p.raw()).collect();
+        // The plaintext is never longer than the modulus, nor than the ciphertext for SM2.
+        let key_len = (self.info().key_size() as usize + 7) / 8;
+        let mut res = Secret::zeroed(key_len.max(src.len()));
+        let mut res_size = res.expose().len() as u32;
+        match unsafe {
+            raw::TEE_AsymmetricDecrypt(
+                self.handle(),
+                p.as_ptr() as _,
+                params.len() as u32,
+                src.as_ptr() as _,
+                src.len() as u32,
+                res.expose_mut().as_mut_ptr() as _,
+                &mut res_size,
+            )
+        } {
+            raw::TEE_SUCCESS => {
+                res.truncate(res_size as usize);
+                Ok(res)
+            }
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
     /// Sign a message digest.
     ///
     /// # Parameters
     ///
     /// 1) `params`: Optional operation parameters.
     /// 2) `digest`: Input buffer containing the input message digest.
-    /// 3) `signature`: Output buffer written with the signature of the digest.
+    /// 3) `signature`: Output buffer written with the signature of the digest. For the ECDSA
+    ///    algorithms the signature is the concatenation of `r` and `s`, each padded to the size
+    ///    of the curve, so the buffer needs twice the byte length of the key size.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let hash = [0u8; 32];
+    /// let mut signature = [0u8; 64];
+    /// let key = TransientObject::allocate(TransientObjectType::EcdsaKeypair, 256)?;
+    /// key.generate_key(256, &[AttributeValue::from_curve(ElementId::EccCurveNistP256).into()])?;
+    /// let operation = Asymmetric::allocate(AlgorithmId::EcdsaP256, OperationMode::Sign, 256)?;
+    /// operation.set_key(&key)?;
+    /// let size = operation.sign_digest(&[], &hash, &mut signature)?;
+    /// ```
     ///
     /// # Errors
     ///
@@ -1348,10 +1670,10 @@ impl Asymmetric {
     ///
     /// # Panics
     ///
-    /// 1) If the algorithm is not a valid algorithm for [Sign](OperationMode::Sign] of
+    /// 1) If the algorithm is not a valid algorithm for [Sign](OperationMode::Sign) of
     ///    `Asymmetric`.
     /// 2) If no key is programmed in the operation.
-    /// 3) If the mode is not set as [Sign](OperationMode::Sign].
+    /// 3) If the mode is not set as [Sign](OperationMode::Sign).
     /// 4) If `digest.len()` is not equal to the hash size of the algorithm.
     /// 3) Hardware or cryptographic algorithm failure.
     /// 4) If the Implementation detects any other error.
@@ -1387,7 +1709,9 @@ impl Asymmetric {
     ///
     /// 1) `params`: Optional operation parameters.
     /// 2) `digest`: Input buffer containing the input message digest.
-    /// 3) `signature`: Input buffer containing the signature to verify.
+    /// 3) `signature`: Input buffer containing the signature to verify. For the ECDSA
+    ///    algorithms it is the concatenation of `r` and `s` as produced by
+    ///    [sign_digest](Asymmetric::sign_digest).
     ///
     /// # Errors
     ///
@@ -1395,10 +1719,10 @@ impl Asymmetric {
     ///
     /// # Panics
     ///
-    /// 1) If the algorithm is not a valid algorithm for [Verify](OperationMode::Verify] of
+    /// 1) If the algorithm is not a valid algorithm for [Verify](OperationMode::Verify) of
     ///    `Asymmetric`.
     /// 2) If no key is programmed in the operation.
-    /// 3) If the mode is not set as [Verify](OperationMode::Verify].
+    /// 3) If the mode is not set as [Verify](OperationMode::Verify).
     /// 4) If `digest.len()` is not equal to the hash size of the algorithm.
     /// 3) Hardware or cryptographic algorithm failure.
     /// 4) If the Implementation detects any other error.
@@ -1425,6 +1749,83 @@ impl Asymmetric {
         }
     }
 
+    /// Sign a message with an [Ed25519](AlgorithmId::Ed25519) key pair.
+    ///
+    /// Ed25519 hashes the message itself, so the complete message is passed to the
+    /// operation rather than a digest.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `message`: Input buffer containing the message to sign.
+    /// 2) `context`: Optional context of at most 255 bytes, which selects Ed25519ctx.
+    /// 3) `prehash`: Whether to sign with Ed25519ph, in which case `message` is the
+    ///    SHA-512 digest of the actual message.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = TransientObject::allocate(TransientObjectType::Ed25519Keypair, 256)?;
+    /// key.generate_key(256, &[])?;
+    /// let operation = Asymmetric::allocate(AlgorithmId::Ed25519, OperationMode::Sign, 256)?;
+    /// operation.set_key(&key)?;
+    /// let signature = operation.sign_ed25519(b"message", None, false)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `context` is longer than 255 bytes.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the algorithm is not [Ed25519](AlgorithmId::Ed25519).
+    /// 2) If no key is programmed in the operation.
+    /// 3) If the mode is not set as [Sign](OperationMode::Sign).
+    /// 4) Hardware or cryptographic algorithm failure.
+    /// 5) If the Implementation detects any other error.
+    pub fn sign_ed25519(
+        &self,
+        message: &[u8],
+        context: Option<&[u8]>,
+        prehash: bool,
+    ) -> Result<[u8; 64]> {
+        let mut signature = [0u8; 64];
+        let params = ed25519_params(context, prehash)?;
+        self.sign_digest(&params, message, &mut signature)?;
+        Ok(signature)
+    }
+
+    /// Verify an [Ed25519](AlgorithmId::Ed25519) signature of a message.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `message`: Input buffer containing the signed message.
+    /// 2) `signature`: Input buffer containing the signature to verify.
+    /// 3) `context`: Optional context, SHALL be the one used for signing.
+    /// 4) `prehash`: Whether the signature was made with Ed25519ph.
+    ///
+    /// # Errors
+    ///
+    /// 1) `SignatureInvalid`: If the signature is invalid.
+    /// 2) `BadParameters`: If `context` is longer than 255 bytes.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the algorithm is not [Ed25519](AlgorithmId::Ed25519).
+    /// 2) If no key is programmed in the operation.
+    /// 3) If the mode is not set as [Verify](OperationMode::Verify).
+    /// 4) Hardware or cryptographic algorithm failure.
+    /// 5) If the Implementation detects any other error.
+    pub fn verify_ed25519(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        context: Option<&[u8]>,
+        prehash: bool,
+    ) -> Result<()> {
+        let params = ed25519_params(context, prehash)?;
+        self.verify_digest(&params, message, signature)
+    }
+
     /// Create an Asymmetric operation without any specific algorithm or other data.
     pub fn null() -> Self {
         Self(OperationHandle::null())
@@ -1444,7 +1845,7 @@ impl Asymmetric {
     }
 
     /// Function usage is similar to [Digest::info_multiple](Digest::info_multiple).
-    pub fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
+    pub fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
         self.0.info_multiple(info_buf)
     }
 
@@ -1459,6 +1860,20 @@ impl Asymmetric {
     }
 }
 
+fn ed25519_params(context: Option<&[u8]>, prehash: bool) -> Result<Vec<Attribute>> {
+    let mut params: Vec<Attribute> = Vec::new();
+    if let Some(ctx) = context {
+        if ctx.len() > 255 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        params.push(AttributeMemref::from_ref(AttributeId::EddsaCtx, ctx).into());
+    }
+    if prehash {
+        params.push(AttributeValue::from_value(AttributeId::EddsaPrehash, 1, 0).into());
+    }
+    Ok(params)
+}
+
 impl OpHandle for Asymmetric {
     fn handle(&self) -> raw::TEE_OperationHandle {
         self.0.handle()
@@ -1476,7 +1891,22 @@ impl DeriveKey {
     ///
     /// 1) `params`: For algorithm [DhDeriveSharedSecret][AlgorithmId::DhDeriveSharedSecret],
     ///    [DhPublicValue](../object/enum.AttributeId.html#variant.DhPublicValue) is required as
-    ///    the passed in attribute.
+    ///    the passed in attribute. For the `Ecdh*` algorithms, the peer public key is passed as
+    ///    [EccPublicValueX](../object/enum.AttributeId.html#variant.EccPublicValueX) and
+    ///    [EccPublicValueY](../object/enum.AttributeId.html#variant.EccPublicValueY), see
+    ///    [derive_ecdh](DeriveKey::derive_ecdh). For [Sm2Kep](AlgorithmId::Sm2Kep), the
+    ///    operation keys are set with [set_key_2](DeriveKey::set_key_2) and the required
+    ///    attributes are the peer public key
+    ///    ([EccPublicValueX](../object/enum.AttributeId.html#variant.EccPublicValueX),
+    ///    [EccPublicValueY](../object/enum.AttributeId.html#variant.EccPublicValueY)), the peer
+    ///    ephemeral public key
+    ///    ([EccEphemeralPublicValueX](../object/enum.AttributeId.html#variant.EccEphemeralPublicValueX),
+    ///    [EccEphemeralPublicValueY](../object/enum.AttributeId.html#variant.EccEphemeralPublicValueY)),
+    ///    both identities
+    ///    ([Sm2IdInitiator](../object/enum.AttributeId.html#variant.Sm2IdInitiator),
+    ///    [Sm2IdResponder](../object/enum.AttributeId.html#variant.Sm2IdResponder)) and the role
+    ///    ([Sm2KepUser](../object/enum.AttributeId.html#variant.Sm2KepUser)). The confirmation
+    ///    attributes are optional.
     /// 2) `object`: An uninitialized transient object to be filled with the derived key.
     ///
     /// # Example
@@ -1539,14 +1969,104 @@ impl DeriveKey {
         };
     }
 
+    /// Derive a secret of `len` bytes into a [Secret](../secret/struct.Secret.html) buffer,
+    /// which is wiped when dropped, instead of a key object. The parameters and panics are the
+    /// same as [derive](DeriveKey::derive).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let attr_public = AttributeMemref::from_ref(AttributeId::DhPublicValue, &peer_public);
+    /// let shared_secret = operation.derive_secret(&[attr_public.into()], 256)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `OutOfMemory`: If not enough resources are available to allocate the object.
+    /// 2) `NotSupported`: If `len` is not a supported size of a generic secret.
+    pub fn derive_secret(&self, params: &[Attribute], len: usize) -> Result<Secret<Vec<u8>>> {
+        let mut object = TransientObject::allocate(TransientObjectType::GenericSecret, len * 8)?;
+        self.derive(params, &mut object);
+        object.ref_attribute_secret(AttributeId::SecretValue)
+    }
+
+    /// Derive a shared secret with the ECDH key pair programmed in the operation and the
+    /// public key of the peer.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `public_x`: X coordinate of the public key of the peer.
+    /// 2) `public_y`: Y coordinate of the public key of the peer.
+    /// 3) `object`: An uninitialized [GenericSecret](../object/enum.TransientObjectType.html#variant.GenericSecret)
+    ///    transient object to be filled with the shared secret.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let curve = AttributeValue::from_curve(ElementId::EccCurveNistP256);
+    /// let key_pair = TransientObject::allocate(TransientObjectType::EcdhKeypair, 256)?;
+    /// key_pair.generate_key(256, &[curve.into()])?;
+    /// let operation = DeriveKey::allocate(AlgorithmId::EcdhP256, 256)?;
+    /// operation.set_key(&key_pair)?;
+    /// let mut secret = TransientObject::allocate(TransientObjectType::GenericSecret, 256)?;
+    /// operation.derive_ecdh(&peer_x, &peer_y, &mut secret);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// 1) If the algorithm is not one of the `Ecdh*` algorithms.
+    /// 2) If the public key is not a point on the curve of the key pair.
+    /// 3) If the `object` is too small for generated value.
+    /// 4) If no key is programmed in the operation.
+    /// 5) Hardware or cryptographic algorithm failure.
+    /// 6) If the Implementation detects any other error.
+    pub fn derive_ecdh(&self, public_x: &[u8], public_y: &[u8], object: &mut TransientObject) {
+        let attr_x = AttributeMemref::from_ref(AttributeId::EccPublicValueX, public_x);
+        let attr_y = AttributeMemref::from_ref(AttributeId::EccPublicValueY, public_y);
+        self.derive(&[attr_x.into(), attr_y.into()], object)
+    }
+
+    /// Derive a shared secret with the [X25519](AlgorithmId::X25519) key pair programmed in the
+    /// operation and the public key of the peer.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `public`: The 32 byte public key of the peer.
+    /// 2) `object`: An uninitialized [GenericSecret](../object/enum.TransientObjectType.html#variant.GenericSecret)
+    ///    transient object to be filled with the shared secret.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key_pair = TransientObject::allocate(TransientObjectType::X25519Keypair, 256)?;
+    /// key_pair.generate_key(256, &[])?;
+    /// let operation = DeriveKey::allocate(AlgorithmId::X25519, 256)?;
+    /// operation.set_key(&key_pair)?;
+    /// let mut secret = TransientObject::allocate(TransientObjectType::GenericSecret, 256)?;
+    /// operation.derive_x25519(&peer_public, &mut secret);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// 1) If the algorithm is not [X25519](AlgorithmId::X25519).
+    /// 2) If the `object` is too small for generated value.
+    /// 3) If no key is programmed in the operation.
+    /// 4) Hardware or cryptographic algorithm failure.
+    /// 5) If the Implementation detects any other error.
+    pub fn derive_x25519(&self, public: &[u8], object: &mut TransientObject) {
+        let attr = AttributeMemref::from_ref(AttributeId::X25519PublicValue, public);
+        self.derive(&[attr.into()], object)
+    }
+
     /// Create a DeriveKey operation without any specific algorithm or other data.
     pub fn null() -> Self {
         Self(OperationHandle::null())
     }
 
     /// Function usage is similar to [Digest::allocate](Digest::allocate).
-    /// Currently only supports [DhDeriveSharedSecret][AlgorithmId::DhDeriveSharedSecret] as
-    /// `algo`.
+    /// Currently only supports [DhDeriveSharedSecret][AlgorithmId::DhDeriveSharedSecret], the
+    /// `Ecdh*` algorithms, [X25519](AlgorithmId::X25519), [Sm2Kep](AlgorithmId::Sm2Kep) and the
+    /// KDF extension algorithms (`Hkdf*`, `ConcatKdf*`, `Pbkdf2HmacSha1`) as `algo`.
     pub fn allocate(algo: AlgorithmId, max_key_size: usize) -> Result<Self> {
         match OperationHandle::allocate(algo, OperationMode::Derive, max_key_size) {
             Ok(handle) => Ok(Self(handle)),
@@ -1560,7 +2080,7 @@ impl DeriveKey {
     }
 
     /// Function usage is similar to [Digest::info_multiple](Digest::info_multiple).
-    pub fn info_multiple(&self, info_buf: &mut [u8]) -> Result<OperationInfoMultiple> {
+    pub fn info_multiple<'a>(&self, info_buf: &'a mut [u8]) -> Result<OperationInfoMultiple<'a>> {
         self.0.info_multiple(info_buf)
     }
 
@@ -1569,6 +2089,19 @@ impl DeriveKey {
         self.0.set_key(object)
     }
 
+    /// Function usage is similar to [Cipher::set_key_2](Cipher::set_key_2), for
+    /// [Sm2Kep](AlgorithmId::Sm2Kep) `object1` is the long term
+    /// [Sm2KepKeypair](../object/enum.TransientObjectType.html#variant.Sm2KepKeypair) and
+    /// `object2` the ephemeral one.
+    pub fn set_key_2<T: ObjHandle, D: ObjHandle>(&self, object1: &T, object2: &D) -> Result<()> {
+        match unsafe {
+            raw::TEE_SetOperationKey2(self.handle(), object1.handle(), object2.handle())
+        } {
+            raw::TEE_SUCCESS => return Ok(()),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
     /// Function usage is similar to [Digest::copy](Digest::copy).
     pub fn copy<T: OpHandle>(&mut self, src: &T) {
         self.0.copy(src)
@@ -1609,7 +2142,65 @@ impl Random {
     }
 }
 
+/// A cryptographically secure random number generator backed by
+/// `TEE_GenerateRandom`.
+///
+/// `TeeRng` holds no state, every request is forwarded to [Random](Random). It
+/// implements the `rand_core` traits, so it can be passed to any crate that
+/// accepts an `RngCore + CryptoRng` generator.
+///
+/// # Example
+///
+/// ```no_run
+/// use rand_core::RngCore;
+/// let mut rng = TeeRng;
+/// let nonce = rng.next_u64();
+/// ```
+#[cfg(feature = "rng")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TeeRng;
+
+#[cfg(feature = "rng")]
+impl rand_core::RngCore for TeeRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        Random::generate(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        Random::generate(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        Random::generate(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+        Random::generate(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rng")]
+impl rand_core::CryptoRng for TeeRng {}
+
+/// Backend for the `getrandom` crate, which has no native source of entropy
+/// on OP-TEE. Registering it lets dependencies such as `rand::thread_rng` or
+/// `uuid` draw their randomness from `TEE_GenerateRandom`.
+#[cfg(feature = "rng")]
+fn getrandom_tee(dest: &mut [u8]) -> std::result::Result<(), getrandom::Error> {
+    Random::generate(dest);
+    Ok(())
+}
+
+#[cfg(feature = "rng")]
+getrandom::register_custom_getrandom!(getrandom_tee);
+
 /// Algorithms that can be allocated as an crypto operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum AlgorithmId {
     /// [Cipher](Cipher) supported algorithm.
@@ -1648,6 +2239,12 @@ pub enum AlgorithmId {
     Des3CbcMacNopad = 0x30000113,
     /// [Mac](Mac) supported algorithm.
     Des3CbcMacPkcs5 = 0x30000513,
+    /// [Cipher](Cipher) supported algorithm.
+    Sm4EcbNopad = 0x10000014,
+    /// [Cipher](Cipher) supported algorithm.
+    Sm4CbcNopad = 0x10000114,
+    /// [Cipher](Cipher) supported algorithm.
+    Sm4Ctr = 0x10000214,
     /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
     /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
     RsassaPkcs1V15MD5 = 0x70001830,
@@ -1714,8 +2311,73 @@ pub enum AlgorithmId {
     /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
     /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
     DSASha256 = 0x70004131,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP192 = 0x70001041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP224 = 0x70002041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP256 = 0x70003041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP384 = 0x70004041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP521 = 0x70005041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    /// The whole message is signed, see [sign_ed25519](Asymmetric::sign_ed25519).
+    Ed25519 = 0x70006043,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    /// The digest to sign is the SM3 hash of `Z_A || M` as defined in GB/T 32918.2.
+    Sm2DsaSm3 = 0x70006045,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Encrypt](OperationMode::Encrypt) or [Decrypt](OperationMode::Decrypt) mode.
+    Sm2Pke = 0x80000045,
     /// [DeriveKey](DeriveKey) supported algorithm.
     DhDeriveSharedSecret = 0x80000032,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP192 = 0x80001042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP224 = 0x80002042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP256 = 0x80003042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP384 = 0x80004042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP521 = 0x80005042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    X25519 = 0x80000044,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [derive](DeriveKey::derive) for the
+    /// required parameters.
+    Sm2Kep = 0x60000045,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [hkdf](../kdf/fn.hkdf.html).
+    HkdfMd5 = 0x800010C0,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [hkdf](../kdf/fn.hkdf.html).
+    HkdfSha1 = 0x800020C0,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [hkdf](../kdf/fn.hkdf.html).
+    HkdfSha224 = 0x800030C0,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [hkdf](../kdf/fn.hkdf.html).
+    HkdfSha256 = 0x800040C0,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [hkdf](../kdf/fn.hkdf.html).
+    HkdfSha384 = 0x800050C0,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [hkdf](../kdf/fn.hkdf.html).
+    HkdfSha512 = 0x800060C0,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [concat_kdf](../kdf/fn.concat_kdf.html).
+    ConcatKdfSha1 = 0x800020C1,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [concat_kdf](../kdf/fn.concat_kdf.html).
+    ConcatKdfSha224 = 0x800030C1,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [concat_kdf](../kdf/fn.concat_kdf.html).
+    ConcatKdfSha256 = 0x800040C1,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [concat_kdf](../kdf/fn.concat_kdf.html).
+    ConcatKdfSha384 = 0x800050C1,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [concat_kdf](../kdf/fn.concat_kdf.html).
+    ConcatKdfSha512 = 0x800060C1,
+    /// [DeriveKey](DeriveKey) supported algorithm, see [pbkdf2](../kdf/fn.pbkdf2.html).
+    Pbkdf2HmacSha1 = 0x800020C2,
     /// [Digest](Digest) supported algorithm.
     Md5 = 0x50000001,
     /// [Digest](Digest) supported algorithm.
@@ -1730,6 +2392,22 @@ pub enum AlgorithmId {
     Sha512 = 0x50000006,
     /// [Mac](Mac) supported algorithm.
     Md5Sha1 = 0x5000000F,
+    /// [Digest](Digest) supported algorithm.
+    Sm3 = 0x50000007,
+    /// [Digest](Digest) supported algorithm.
+    Sha3_224 = 0x50000008,
+    /// [Digest](Digest) supported algorithm.
+    Sha3_256 = 0x50000009,
+    /// [Digest](Digest) supported algorithm.
+    Sha3_384 = 0x5000000A,
+    /// [Digest](Digest) supported algorithm.
+    Sha3_512 = 0x5000000B,
+    /// [Digest](Digest) supported extendable-output algorithm, see
+    /// [squeeze](Digest::squeeze).
+    Shake128 = 0x50000101,
+    /// [Digest](Digest) supported extendable-output algorithm, see
+    /// [squeeze](Digest::squeeze).
+    Shake256 = 0x50000102,
     /// [Mac](Mac) supported algorithm.
     HmacMd5 = 0x30000001,
     /// [Mac](Mac) supported algorithm.
@@ -1742,11 +2420,141 @@ pub enum AlgorithmId {
     HmacSha384 = 0x30000005,
     /// [Mac](Mac) supported algorithm.
     HmacSha512 = 0x30000006,
+    /// [Mac](Mac) supported algorithm.
+    HmacSm3 = 0x30000007,
+    /// [Mac](Mac) supported algorithm.
+    HmacSha3_224 = 0x30000008,
+    /// [Mac](Mac) supported algorithm.
+    HmacSha3_256 = 0x30000009,
+    /// [Mac](Mac) supported algorithm.
+    HmacSha3_384 = 0x3000000A,
+    /// [Mac](Mac) supported algorithm.
+    HmacSha3_512 = 0x3000000B,
     /// Reserved for GlobalPlatform compliance test applications.
     IllegalValue = 0xefffffff,
 }
 
+impl AlgorithmId {
+    /// Return the algorithm identified by `raw`, or `None` if it is not a known algorithm.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0x10000010 => Some(AlgorithmId::AesEcbNopad),
+            0x10000110 => Some(AlgorithmId::AesCbcNopad),
+            0x10000210 => Some(AlgorithmId::AesCtr),
+            0x10000310 => Some(AlgorithmId::AesCts),
+            0x10000410 => Some(AlgorithmId::AesXts),
+            0x30000110 => Some(AlgorithmId::AesCbcMacNopad),
+            0x30000510 => Some(AlgorithmId::AesCbcMacPkcs5),
+            0x30000610 => Some(AlgorithmId::AesCmac),
+            0x40000710 => Some(AlgorithmId::AesCcm),
+            0x40000810 => Some(AlgorithmId::AesGcm),
+            0x10000011 => Some(AlgorithmId::DesEcbNopad),
+            0x10000111 => Some(AlgorithmId::DesCbcNopad),
+            0x30000111 => Some(AlgorithmId::DesCbcMacNopad),
+            0x30000511 => Some(AlgorithmId::DesCbcMacPkcs5),
+            0x10000013 => Some(AlgorithmId::Des3EcbNopad),
+            0x10000113 => Some(AlgorithmId::Des3CbcNopad),
+            0x30000113 => Some(AlgorithmId::Des3CbcMacNopad),
+            0x30000513 => Some(AlgorithmId::Des3CbcMacPkcs5),
+            0x10000014 => Some(AlgorithmId::Sm4EcbNopad),
+            0x10000114 => Some(AlgorithmId::Sm4CbcNopad),
+            0x10000214 => Some(AlgorithmId::Sm4Ctr),
+            0x70001830 => Some(AlgorithmId::RsassaPkcs1V15MD5),
+            0x70002830 => Some(AlgorithmId::RsassaPkcs1V15Sha1),
+            0x70003830 => Some(AlgorithmId::RsassaPkcs1V15Sha224),
+            0x70004830 => Some(AlgorithmId::RsassaPkcs1V15Sha256),
+            0x70005830 => Some(AlgorithmId::RsassaPkcs1V15Sha384),
+            0x70006830 => Some(AlgorithmId::RsassaPkcs1V15Sha512),
+            0x7000F830 => Some(AlgorithmId::RsassaPkcs1V15MD5Sha1),
+            0x70212930 => Some(AlgorithmId::RsassaPkcs1PssMgf1Sha1),
+            0x70313930 => Some(AlgorithmId::RsassaPkcs1PssMgf1Sha224),
+            0x70414930 => Some(AlgorithmId::RsassaPkcs1PssMgf1Sha256),
+            0x70515930 => Some(AlgorithmId::RsassaPkcs1PssMgf1Sha384),
+            0x70616930 => Some(AlgorithmId::RsassaPkcs1PssMgf1Sha512),
+            0x60000130 => Some(AlgorithmId::RsaesPkcs1V15),
+            0x60210230 => Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha1),
+            0x60310230 => Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha224),
+            0x60410230 => Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha256),
+            0x60510230 => Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha384),
+            0x60610230 => Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha512),
+            0x60000030 => Some(AlgorithmId::RsaNopad),
+            0x70002131 => Some(AlgorithmId::DSASha1),
+            0x70003131 => Some(AlgorithmId::DSASha224),
+            0x70004131 => Some(AlgorithmId::DSASha256),
+            0x70001041 => Some(AlgorithmId::EcdsaP192),
+            0x70002041 => Some(AlgorithmId::EcdsaP224),
+            0x70003041 => Some(AlgorithmId::EcdsaP256),
+            0x70004041 => Some(AlgorithmId::EcdsaP384),
+            0x70005041 => Some(AlgorithmId::EcdsaP521),
+            0x70006043 => Some(AlgorithmId::Ed25519),
+            0x70006045 => Some(AlgorithmId::Sm2DsaSm3),
+            0x80000045 => Some(AlgorithmId::Sm2Pke),
+            0x80000032 => Some(AlgorithmId::DhDeriveSharedSecret),
+            0x80001042 => Some(AlgorithmId::EcdhP192),
+            0x80002042 => Some(AlgorithmId::EcdhP224),
+            0x80003042 => Some(AlgorithmId::EcdhP256),
+            0x80004042 => Some(AlgorithmId::EcdhP384),
+            0x80005042 => Some(AlgorithmId::EcdhP521),
+            0x80000044 => Some(AlgorithmId::X25519),
+            0x60000045 => Some(AlgorithmId::Sm2Kep),
+            0x800010C0 => Some(AlgorithmId::HkdfMd5),
+            0x800020C0 => Some(AlgorithmId::HkdfSha1),
+            0x800030C0 => Some(AlgorithmId::HkdfSha224),
+            0x800040C0 => Some(AlgorithmId::HkdfSha256),
+            0x800050C0 => Some(AlgorithmId::HkdfSha384),
+            0x800060C0 => Some(AlgorithmId::HkdfSha512),
+            0x800020C1 => Some(AlgorithmId::ConcatKdfSha1),
+            0x800030C1 => Some(AlgorithmId::ConcatKdfSha224),
+            0x800040C1 => Some(AlgorithmId::ConcatKdfSha256),
+            0x800050C1 => Some(AlgorithmId::ConcatKdfSha384),
+            0x800060C1 => Some(AlgorithmId::ConcatKdfSha512),
+            0x800020C2 => Some(AlgorithmId::Pbkdf2HmacSha1),
+            0x50000001 => Some(AlgorithmId::Md5),
+            0x50000002 => Some(AlgorithmId::Sha1),
+            0x50000003 => Some(AlgorithmId::Sha224),
+            0x50000004 => Some(AlgorithmId::Sha256),
+            0x50000005 => Some(AlgorithmId::Sha384),
+            0x50000006 => Some(AlgorithmId::Sha512),
+            0x5000000F => Some(AlgorithmId::Md5Sha1),
+            0x50000007 => Some(AlgorithmId::Sm3),
+            0x50000008 => Some(AlgorithmId::Sha3_224),
+            0x50000009 => Some(AlgorithmId::Sha3_256),
+            0x5000000A => Some(AlgorithmId::Sha3_384),
+            0x5000000B => Some(AlgorithmId::Sha3_512),
+            0x50000101 => Some(AlgorithmId::Shake128),
+            0x50000102 => Some(AlgorithmId::Shake256),
+            0x30000001 => Some(AlgorithmId::HmacMd5),
+            0x30000002 => Some(AlgorithmId::HmacSha1),
+            0x30000003 => Some(AlgorithmId::HmacSha224),
+            0x30000004 => Some(AlgorithmId::HmacSha256),
+            0x30000005 => Some(AlgorithmId::HmacSha384),
+            0x30000006 => Some(AlgorithmId::HmacSha512),
+            0x30000007 => Some(AlgorithmId::HmacSm3),
+            0x30000008 => Some(AlgorithmId::HmacSha3_224),
+            0x30000009 => Some(AlgorithmId::HmacSha3_256),
+            0x3000000A => Some(AlgorithmId::HmacSha3_384),
+            0x3000000B => Some(AlgorithmId::HmacSha3_512),
+            0xefffffff => Some(AlgorithmId::IllegalValue),
+            _ => None,
+        }
+    }
+
+    // The digest signed with an ECDSA algorithm. The algorithm only encodes the curve, each
+    // curve is paired with the digest of matching strength.
+    pub(crate) fn ecdsa_digest(&self) -> Option<AlgorithmId> {
+        match self {
+            AlgorithmId::EcdsaP192 => Some(AlgorithmId::Sha1),
+            AlgorithmId::EcdsaP224 => Some(AlgorithmId::Sha224),
+            AlgorithmId::EcdsaP256 => Some(AlgorithmId::Sha256),
+            AlgorithmId::EcdsaP384 => Some(AlgorithmId::Sha384),
+            AlgorithmId::EcdsaP521 => Some(AlgorithmId::Sha512),
+            _ => None,
+        }
+    }
+}
+
 /// This specification defines support for optional cryptographic elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum ElementId {
     /// Where algId fully defines the required support,
@@ -1762,4 +2570,24 @@ pub enum ElementId {
     EccCurveNistP384 = 0x00000004,
     /// Source: `NIST`, Generic: `Y`, Size: 521 bits
     EccCurveNistP521 = 0x00000005,
+    /// Source: `OSCCA`, Generic: `N`, Size: 256 bits
+    EccCurveSm2 = 0x00000300,
+}
+
+impl ElementId {
+    /// Return the key size in bits of an elliptic curve, which is the `key_size` to pass to
+    /// [TransientObject::allocate](../object/struct.TransientObject.html#method.allocate) and
+    /// [generate_key](../object/struct.TransientObject.html#method.generate_key) for keys on
+    /// this curve. Return 0 for [ElementNone](ElementId::ElementNone).
+    pub fn key_size(&self) -> usize {
+        match self {
+            ElementId::ElementNone => 0,
+            ElementId::EccCurveNistP192 => 192,
+            ElementId::EccCurveNistP224 => 224,
+            ElementId::EccCurveNistP256 => 256,
+            ElementId::EccCurveNistP384 => 384,
+            ElementId::EccCurveNistP521 => 521,
+            ElementId::EccCurveSm2 => 256,
+        }
+    }
 }
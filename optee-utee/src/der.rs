@@ -0,0 +1,295 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+// Minimal DER reader and writer, plus PEM armoring, shared by the key, certificate and
+// certificate request encoders. Only the subset of X.690 used by those structures is
+// handled: single byte tags and definite lengths up to 4 bytes.
+
+use crate::{Error, ErrorKind, Result, Secret};
+
+pub(crate) const TAG_BOOLEAN: u8 = 0x01;
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_UTF8_STRING: u8 = 0x0C;
+pub(crate) const TAG_PRINTABLE_STRING: u8 = 0x13;
+pub(crate) const TAG_UTC_TIME: u8 = 0x17;
+pub(crate) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+
+/// Tag of the constructed context specific field `[n]`.
+pub(crate) const fn context(n: u8) -> u8 {
+    0xA0 | n
+}
+
+fn bad_format() -> Error {
+    Error::new(ErrorKind::BadFormat)
+}
+
+/// A decoded TLV: its tag, its content and the whole encoding including the header.
+#[derive(Clone, Copy)]
+pub(crate) struct Tlv<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+    pub encoded: &'a [u8],
+}
+
+/// Sequential reader over the elements of a DER encoded structure.
+#[derive(Clone, Copy)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    pub fn read(&mut self) -> Result<Tlv<'a>> {
+        let data = self.data;
+        if data.len() < 2 || data[0] & 0x1F == 0x1F {
+            return Err(bad_format());
+        }
+        let (len, header) = match data[1] {
+            n if n < 0x80 => (n as usize, 2),
+            n if n > 0x80 && n <= 0x84 => {
+                let count = (n & 0x7F) as usize;
+                if data.len() < 2 + count {
+                    return Err(bad_format());
+                }
+                let len = data[2..2 + count]
+                    .iter()
+                    .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+                (len, 2 + count)
+            }
+            _ => return Err(bad_format()),
+        };
+        if data.len() - header < len {
+            return Err(bad_format());
+        }
+        self.data = &data[header + len..];
+        Ok(Tlv {
+            tag: data[0],
+            value: &data[header..header + len],
+            encoded: &data[..header + len],
+        })
+    }
+
+    pub fn expect(&mut self, tag: u8) -> Result<&'a [u8]> {
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            return Err(bad_format());
+        }
+        Ok(tlv.value)
+    }
+
+    /// Read the element if it has the given tag, used for OPTIONAL and DEFAULT fields.
+    pub fn optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        match self.peek_tag() {
+            Some(t) if t == tag => self.expect(tag).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Read an element with the given tag, returning its whole encoding.
+    pub fn expect_encoded(&mut self, tag: u8) -> Result<&'a [u8]> {
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            return Err(bad_format());
+        }
+        Ok(tlv.encoded)
+    }
+
+    pub fn sequence(&mut self) -> Result<Reader<'a>> {
+        self.expect(TAG_SEQUENCE).map(Reader::new)
+    }
+
+    /// Read a non-negative INTEGER as big-endian bytes without the sign padding.
+    pub fn unsigned(&mut self) -> Result<&'a [u8]> {
+        let value = self.expect(TAG_INTEGER)?;
+        match value {
+            [] => Err(bad_format()),
+            [first, ..] if first & 0x80 != 0 => Err(bad_format()),
+            [0, rest @ ..] if !rest.is_empty() => Ok(rest),
+            _ => Ok(value),
+        }
+    }
+
+    pub fn small_unsigned(&mut self) -> Result<u32> {
+        let value = self.unsigned()?;
+        if value.len() > 4 {
+            return Err(bad_format());
+        }
+        Ok(value.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32))
+    }
+
+    /// Read a BOOLEAN with DEFAULT FALSE.
+    pub fn default_false(&mut self) -> Result<bool> {
+        match self.optional(TAG_BOOLEAN)? {
+            None => Ok(false),
+            Some([0x00]) => Ok(false),
+            Some([0xFF]) => Ok(true),
+            Some(_) => Err(bad_format()),
+        }
+    }
+
+    pub fn oid(&mut self) -> Result<&'a [u8]> {
+        self.expect(TAG_OID)
+    }
+
+    pub fn octet_string(&mut self) -> Result<&'a [u8]> {
+        self.expect(TAG_OCTET_STRING)
+    }
+
+    /// Read a BIT STRING as its bytes and the number of unused bits in the last byte.
+    pub fn bits(&mut self) -> Result<(&'a [u8], u8)> {
+        match self.expect(TAG_BIT_STRING)? {
+            [unused, rest @ ..] if *unused < 8 && (*unused == 0 || !rest.is_empty()) => {
+                Ok((rest, *unused))
+            }
+            _ => Err(bad_format()),
+        }
+    }
+
+    /// Read a BIT STRING holding whole bytes.
+    pub fn bit_string(&mut self) -> Result<&'a [u8]> {
+        match self.expect(TAG_BIT_STRING)? {
+            [0, rest @ ..] => Ok(rest),
+            _ => Err(bad_format()),
+        }
+    }
+
+    pub fn finish(&self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(bad_format())
+        }
+    }
+}
+
+/// Decode a complete structure, failing on trailing data.
+pub(crate) fn parse<'a, T, F>(data: &'a [u8], f: F) -> Result<T>
+where
+    F: FnOnce(&mut Reader<'a>) -> Result<T>,
+{
+    let mut reader = Reader::new(data);
+    let res = f(&mut reader)?;
+    reader.finish()?;
+    Ok(res)
+}
+
+pub(crate) fn encode(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(value.len() + 6);
+    res.push(tag);
+    let len = value.len();
+    if len < 0x80 {
+        res.push(len as u8);
+    } else {
+        let bytes = (len as u32).to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        res.push(0x80 | (4 - skip) as u8);
+        res.extend_from_slice(&bytes[skip..]);
+    }
+    res.extend_from_slice(value);
+    res
+}
+
+pub(crate) fn sequence(elements: &[&[u8]]) -> Vec<u8> {
+    encode(TAG_SEQUENCE, &elements.concat())
+}
+
+/// Encode big-endian bytes as a non-negative INTEGER.
+pub(crate) fn unsigned(value: &[u8]) -> Vec<u8> {
+    let skip = value.iter().take_while(|b| **b == 0).count();
+    let value = &value[skip..];
+    match value.first() {
+        None => encode(TAG_INTEGER, &[0]),
+        Some(first) if first & 0x80 != 0 => encode(TAG_INTEGER, &[&[0u8][..], value].concat()),
+        Some(_) => encode(TAG_INTEGER, value),
+    }
+}
+
+pub(crate) fn oid(value: &[u8]) -> Vec<u8> {
+    encode(TAG_OID, value)
+}
+
+pub(crate) fn null() -> Vec<u8> {
+    encode(TAG_NULL, &[])
+}
+
+pub(crate) fn bit_string(value: &[u8]) -> Vec<u8> {
+    bits(value, 0)
+}
+
+/// Encode a BIT STRING whose last `unused` bits are padding.
+pub(crate) fn bits(value: &[u8], unused: u8) -> Vec<u8> {
+    encode(TAG_BIT_STRING, &[&[unused][..], value].concat())
+}
+
+pub(crate) fn boolean(value: bool) -> Vec<u8> {
+    encode(TAG_BOOLEAN, &[if value { 0xFF } else { 0 }])
+}
+
+pub(crate) fn octet_string(value: &[u8]) -> Vec<u8> {
+    encode(TAG_OCTET_STRING, value)
+}
+
+/// Decode the first PEM block of `pem`, returning its label and content.
+pub(crate) fn pem_decode(pem: &str) -> Result<(String, Vec<u8>)> {
+    const BEGIN: &str = "-----BEGIN ";
+    const END: &str = "-----END ";
+    let start = pem.find(BEGIN).ok_or_else(bad_format)? + BEGIN.len();
+    let label_len = pem[start..].find("-----").ok_or_else(bad_format)?;
+    let label = &pem[start..start + label_len];
+    let body_start = start + label_len + 5;
+    let footer = format!("{}{}-----", END, label);
+    let body_len = pem[body_start..].find(&footer).ok_or_else(bad_format)?;
+    let body = &pem[body_start..body_start + body_len];
+    // Encrypted legacy PEM carries headers such as `Proc-Type`.
+    if body.contains(':') {
+        return Err(Error::new(ErrorKind::NotSupported));
+    }
+    // The encoded content of a private key is as sensitive as the key.
+    let base64: Secret<Vec<u8>> =
+        Secret::new(body.bytes().filter(|b| !b.is_ascii_whitespace()).collect());
+    let der = base64::decode(base64.expose()).map_err(|_| bad_format())?;
+    Ok((label.to_string(), der))
+}
+
+pub(crate) fn pem_encode(label: &str, der: &[u8]) -> String {
+    let base64 = base64::encode(der);
+    let mut res = format!("-----BEGIN {}-----\n", label);
+    for line in base64.as_bytes().chunks(64) {
+        // base64 output is always ASCII
+        res.push_str(std::str::from_utf8(line).unwrap());
+        res.push('\n');
+    }
+    res.push_str(&format!("-----END {}-----\n", label));
+    res
+}
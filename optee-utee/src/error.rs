@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::{ParamType, Parameter};
 use optee_utee_sys as raw;
 use std::convert::From;
 use std::fmt;
@@ -31,8 +32,15 @@ use std::fmt;
 /// ````
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The first code of the range left to application-defined errors, see [app](Error::app).
+pub const APP_ERROR_MIN: u32 = 0x0000_0001;
+/// The last code of the range left to application-defined errors. The codes of the
+/// GlobalPlatform specifications and of OP-TEE are all above it.
+pub const APP_ERROR_MAX: u32 = 0x7FFF_FFFF;
+
 pub struct Error {
     code: u32,
+    detail: Option<String>,
 }
 
 /// A list specifying general categories of TEE error and its corresponding code
@@ -102,9 +110,21 @@ pub enum ErrorKind {
     TimeNeedsReset = 0xFFFF5001,
     /// Unknown error.
     Unknown,
+    /// Application-defined error, created with [Error::app](Error::app). The kind covers a range
+    /// of codes and has no code of its own: [Error::new](Error::new) and `From` give a
+    /// `Generic` error for it.
+    Application,
 }
 
 impl ErrorKind {
+    // The code of an error of this kind, `Generic` for the application-defined errors.
+    fn code(self) -> u32 {
+        match self {
+            ErrorKind::Application => raw::TEE_ERROR_GENERIC,
+            kind => kind as u32,
+        }
+    }
+
     pub(crate) fn as_str(&self) -> &'static str {
         match *self {
             ErrorKind::CorruptObject => "Object corruption.",
@@ -143,13 +163,87 @@ impl ErrorKind {
                 "The persistent time has been set but may have been corrupted and SHALL no longer be trusted."
             },
             ErrorKind::Unknown => "Unknown error.",
+            ErrorKind::Application => "Application-defined error.",
         }
     }
 }
 
 impl Error {
+    /// Creates an error of `kind`. `ErrorKind::Application` gives a `Generic` error, create an
+    /// application-defined error with [app](Error::app).
     pub fn new(kind: ErrorKind) -> Error {
-        Error { code: kind as u32 }
+        Error {
+            code: kind.code(),
+            detail: None,
+        }
+    }
+
+    /// Creates an application-defined error, returned by the TA as its `code`.
+    ///
+    /// A message can be attached with [with_detail](Error::with_detail). When the entry point
+    /// is declared with `#[ta_invoke_command(error_message = N)]`, the message is written to
+    /// the output memref `N` of the failed command, where the client reads it back with
+    /// `optee_teec::AppError::decode`.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// const ERROR_QUOTA_EXCEEDED: u32 = 0x1001;
+    ///
+    /// return Err(Error::app(ERROR_QUOTA_EXCEEDED).with_detail("at most 16 keys per client"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// 1) If `code` is not between [APP_ERROR_MIN](APP_ERROR_MIN) and
+    ///    [APP_ERROR_MAX](APP_ERROR_MAX).
+    pub fn app(code: u32) -> Error {
+        assert!(
+            (APP_ERROR_MIN..=APP_ERROR_MAX).contains(&code),
+            "application error code 0x{:x} out of range",
+            code
+        );
+        Error { code, detail: None }
+    }
+
+    /// Attaches a message giving the context of the error.
+    pub fn with_detail(mut self, detail: &str) -> Error {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    /// Returns the message attached with [with_detail](Error::with_detail).
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// Returns the code of an application-defined error, `None` for the other errors.
+    pub fn app_code(&self) -> Option<u32> {
+        match self.code {
+            APP_ERROR_MIN..=APP_ERROR_MAX => Some(self.code),
+            _ => None,
+        }
+    }
+
+    // Writes the detail to an output memref for `#[ta_invoke_command(error_message = N)]`,
+    // truncated to the memref on a character boundary. The size is set to zero without detail.
+    #[doc(hidden)]
+    pub fn _write_detail(&self, param: &mut Parameter) {
+        let mut memref = match param.param_type {
+            ParamType::MemrefOutput | ParamType::MemrefInout => match unsafe { param.as_memref() } {
+                Ok(memref) => memref,
+                Err(_) => return,
+            },
+            _ => return,
+        };
+        let detail = self.detail().unwrap_or_default();
+        let buffer = memref.buffer();
+        let mut len = detail.len().min(buffer.len());
+        while !detail.is_char_boundary(len) {
+            len -= 1;
+        }
+        buffer[..len].copy_from_slice(&detail.as_bytes()[..len]);
+        memref.set_updated_size(len);
     }
 
     /// Creates a new instance of an `Error` from a particular TEE error code.
@@ -163,7 +257,7 @@ impl Error {
     /// assert_eq!(error.kind(), optee_utee::ErrorKind::Security);
     /// ```
     pub fn from_raw_error(code: u32) -> Error {
-        Error { code }
+        Error { code, detail: None }
     }
 
     /// Returns the corresponding `ErrorKind` for this error.
@@ -206,6 +300,7 @@ impl Error {
             raw::TEE_ERROR_SIGNATURE_INVALID => ErrorKind::SignatureInvalid,
             raw::TEE_ERROR_TIME_NOT_SET => ErrorKind::TimeNotSet,
             raw::TEE_ERROR_TIME_NEEDS_RESET => ErrorKind::TimeNeedsReset,
+            APP_ERROR_MIN..=APP_ERROR_MAX => ErrorKind::Application,
             _ => ErrorKind::Unknown,
         }
     }
@@ -221,13 +316,21 @@ impl Error {
 
 impl fmt::Debug for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{} (error code 0x{:x})", self.message(), self.code)
+        write!(fmt, "{} (error code 0x{:x})", self.message(), self.code)?;
+        match &self.detail {
+            Some(detail) => write!(fmt, ": {}", detail),
+            None => Ok(()),
+        }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{} (error code 0x{:x})", self.message(), self.code)
+        write!(fmt, "{} (error code 0x{:x})", self.message(), self.code)?;
+        match &self.detail {
+            Some(detail) => write!(fmt, ": {}", detail),
+            None => Ok(()),
+        }
     }
 }
 
@@ -240,6 +343,6 @@ impl std::error::Error for Error {
 impl From<ErrorKind> for Error {
     #[inline]
     fn from(kind: ErrorKind) -> Error {
-        Error { code: kind as u32 }
+        Error::new(kind)
     }
 }
@@ -0,0 +1,1112 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A minimal HTTP/1.1 client over [TcpStream](../net/struct.TcpStream.html), and over
+//! [TlsStream](../tls/struct.TlsStream.html) for `https` URLs with the `tls` feature.
+//!
+//! Every request opens its own connection and sends `Connection: close`. Response bodies are
+//! read with `Content-Length`, chunked transfer coding or up to the end of the connection, and
+//! are limited to [max_response_size](Client::set_max_response_size) bytes. Redirects are only
+//! followed once enabled with [set_max_redirects](Client::set_max_redirects).
+//!
+//! Errors are reported as `io::Error`: `InvalidInput` for a bad URL or header,
+//! `InvalidData` for a malformed or too large response, and the socket errors of
+//! [net](../net/index.html).
+//!
+//! # Example
+//!
+//! ```no_run
+//! let mut client = Client::new();
+//! client.set_timeout(Some(Duration::from_secs(10)));
+//! let response = client.get("http://127.0.0.1:8080/crl.der")?;
+//! if response.status() != 200 {
+//!     return Err(io::Error::new(io::ErrorKind::Other, "fetch failed"));
+//! }
+//! let crl = response.into_body();
+//! ```
+//!
+//! A loopback server such as `python3 -m http.server 8080 --bind 127.0.0.1` in the normal
+//! world is enough to exercise a TA against this client.
+
+use crate::net::TcpStream;
+#[cfg(feature = "tls")]
+use crate::tls::{
+    rustls::{ClientConfig, ClientSession},
+    TlsStream,
+};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The default limit of a response body, 1 MiB.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+// Limit of the status line and headers of a response, and of a chunk size line.
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+const MAX_CHUNK_LINE_SIZE: usize = 1024;
+
+// Managed by the client, they can not be set on a request.
+const RESERVED_HEADERS: [&str; 4] = ["host", "content-length", "transfer-encoding", "connection"];
+
+/// The request method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    /// Return the method as sent on the request line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An HTTP request.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut request = Request::post("https://attest.example.com/v1/evidence", evidence)?;
+/// request.set_header("Content-Type", "application/cbor")?;
+/// let response = client.send(&request)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Request {
+    method: Method,
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Create a request without headers or body.
+    ///
+    /// # Errors
+    ///
+    /// 1) `InvalidInput`: If `url` is not an absolute `http` or `https` URL.
+    pub fn new(method: Method, url: &str) -> io::Result<Self> {
+        Ok(Self {
+            method,
+            url: Url::parse(url)?,
+            headers: Vec::new(),
+            body: Vec::new(),
+        })
+    }
+
+    /// Create a `GET` request.
+    pub fn get(url: &str) -> io::Result<Self> {
+        Self::new(Method::Get, url)
+    }
+
+    /// Create a `POST` request sending `body`.
+    pub fn post(url: &str, body: Vec<u8>) -> io::Result<Self> {
+        let mut request = Self::new(Method::Post, url)?;
+        request.body = body;
+        Ok(request)
+    }
+
+    /// Add a header, sent in the order the headers are added.
+    ///
+    /// # Errors
+    ///
+    /// 1) `InvalidInput`: If `name` is not a valid header name, `value` contains a line break,
+    ///    or `name` is one of `Host`, `Content-Length`, `Transfer-Encoding` and `Connection`,
+    ///    which are set by the client.
+    pub fn set_header(&mut self, name: &str, value: &str) -> io::Result<()> {
+        let valid_name = !name.is_empty() && name.bytes().all(is_token);
+        if !valid_name || value.bytes().any(|b| b == b'\r' || b == b'\n' || b == 0) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Invalid header"));
+        }
+        if RESERVED_HEADERS
+            .iter()
+            .any(|h| h.eq_ignore_ascii_case(name))
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Header set by the client",
+            ));
+        }
+        self.headers.push((name.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    /// Replace the body.
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    /// Return the method.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Return the headers added to the request.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Return the body.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+/// An HTTP response.
+#[derive(Clone, Debug)]
+pub struct Response {
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Return the status code.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Return the reason phrase of the status line.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Return the headers, in the order they were received.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Return the value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Return the body, with the transfer coding removed.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Return the body, consuming the response.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+/// An HTTP/1.1 client.
+///
+/// By default the socket timeouts are not set, response bodies are limited to
+/// [DEFAULT_MAX_RESPONSE_SIZE](DEFAULT_MAX_RESPONSE_SIZE) bytes and redirects are returned
+/// to the caller instead of being followed.
+#[derive(Clone)]
+pub struct Client {
+    timeout: Option<Duration>,
+    max_response_size: usize,
+    max_redirects: usize,
+    #[cfg(feature = "tls")]
+    tls_config: Option<Arc<ClientConfig>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Client {
+    /// Create a client with the default settings.
+    pub fn new() -> Self {
+        Self {
+            timeout: None,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            max_redirects: 0,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+        }
+    }
+
+    /// Set the read and write timeout of the connections, `None` blocks indefinitely.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Set the limit of a response body in bytes. A larger response fails with
+    /// `InvalidData`.
+    pub fn set_max_response_size(&mut self, size: usize) {
+        self.max_response_size = size;
+    }
+
+    /// Follow up to `max` redirects, `0` disables redirects.
+    ///
+    /// A `303` response, and a `301` or `302` response to a `POST`, is followed with a `GET`
+    /// without body, as browsers do. The `Authorization` and `Cookie` headers are dropped when
+    /// a redirect leaves the origin of the request.
+    pub fn set_max_redirects(&mut self, max: usize) {
+        self.max_redirects = max;
+    }
+
+    /// Use `config` for `https` URLs, see [client_config](../tls/fn.client_config.html).
+    #[cfg(feature = "tls")]
+    pub fn set_tls_config(&mut self, config: Arc<ClientConfig>) {
+        self.tls_config = Some(config);
+    }
+
+    /// Send a `GET` request for `url`.
+    pub fn get(&self, url: &str) -> io::Result<Response> {
+        self.send(&Request::get(url)?)
+    }
+
+    /// Send a `POST` request of `body` with the given content type.
+    pub fn post(&self, url: &str, content_type: &str, body: &[u8]) -> io::Result<Response> {
+        let mut request = Request::post(url, body.to_vec())?;
+        request.set_header("Content-Type", content_type)?;
+        self.send(&request)
+    }
+
+    /// Send `request` and read the response, following redirects if enabled.
+    ///
+    /// # Errors
+    ///
+    /// 1) `InvalidInput`: If the URL is `https` and no TLS configuration is set, or a redirect
+    ///    points to an invalid URL.
+    /// 2) `InvalidData`: If the response is malformed or its body exceeds the size limit.
+    /// 3) `Other`: If more than the allowed number of redirects are received.
+    pub fn send(&self, request: &Request) -> io::Result<Response> {
+        self.send_with(request, |url| self.connect(url))
+    }
+
+    // Send `request` over the connections opened by `connect`, one per exchange.
+    fn send_with<C, F>(&self, request: &Request, mut connect: F) -> io::Result<Response>
+    where
+        C: Read + Write,
+        F: FnMut(&Url) -> io::Result<C>,
+    {
+        let mut url = request.url.clone();
+        let mut method = request.method;
+        let mut headers = request.headers.clone();
+        let mut body = request.body.as_slice();
+        let mut redirects = 0;
+        loop {
+            let response = self.exchange(connect(&url)?, &url, method, &headers, body)?;
+            let location = match response.status {
+                301 | 302 | 303 | 307 | 308 if self.max_redirects > 0 => {
+                    response.header("Location")
+                }
+                _ => None,
+            };
+            let location = match location {
+                Some(location) => location,
+                None => return Ok(response),
+            };
+            if redirects == self.max_redirects {
+                return Err(io::Error::new(ErrorKind::Other, "Too many redirects"));
+            }
+            redirects += 1;
+
+            let next = url.join(location)?;
+            redirect(
+                response.status,
+                &url,
+                &next,
+                &mut method,
+                &mut headers,
+                &mut body,
+            );
+            url = next;
+        }
+    }
+
+    fn exchange<C: Read + Write>(
+        &self,
+        mut connection: C,
+        url: &Url,
+        method: Method,
+        headers: &[(String, String)],
+        body: &[u8],
+    ) -> io::Result<Response> {
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\n",
+            method,
+            url.path,
+            url.authority()
+        );
+        for (name, value) in headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if method == Method::Post || !body.is_empty() {
+            head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        connection.write_all(head.as_bytes())?;
+        connection.write_all(body)?;
+        connection.flush()?;
+        read_response(&mut BufReader::new(connection), self.max_response_size)
+    }
+
+    fn connect(&self, url: &Url) -> io::Result<Connection> {
+        #[cfg(feature = "tls")]
+        let tls_config = match (url.tls, &self.tls_config) {
+            (false, _) => None,
+            (true, Some(config)) => Some(config),
+            (true, None) => return Err(no_tls_config()),
+        };
+        #[cfg(not(feature = "tls"))]
+        {
+            if url.tls {
+                return Err(no_tls_config());
+            }
+        }
+
+        let mut stream = if url.host.contains(':') {
+            TcpStream::connect_v6(&url.host, url.port)?
+        } else {
+            TcpStream::connect(&url.host, url.port)?
+        };
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+        #[cfg(feature = "tls")]
+        {
+            if let Some(config) = tls_config {
+                let stream = TlsStream::client(stream, &url.host, config)?;
+                return Ok(Connection::Tls(Box::new(stream)));
+            }
+        }
+        Ok(Connection::Plain(stream))
+    }
+}
+
+// Rewrite the request for a redirect with `status` from `from` to `to`. A 303, or a 301 or 302
+// answering a POST, is followed with a GET without body, and the credentials are not sent to
+// another origin.
+fn redirect(
+    status: u16,
+    from: &Url,
+    to: &Url,
+    method: &mut Method,
+    headers: &mut Vec<(String, String)>,
+    body: &mut &[u8],
+) {
+    if status == 303 || (status < 303 && *method == Method::Post) {
+        *method = Method::Get;
+        *body = &[];
+        headers.retain(|(n, _)| !n.eq_ignore_ascii_case("content-type"));
+    }
+    if !to.same_origin(from) {
+        headers.retain(|(n, _)| {
+            !n.eq_ignore_ascii_case("authorization") && !n.eq_ignore_ascii_case("cookie")
+        });
+    }
+}
+
+fn no_tls_config() -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        "https requires a TLS configuration",
+    )
+}
+
+enum Connection {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TlsStream<ClientSession>>),
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+// An absolute `http` or `https` URL, without user information or fragment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Url {
+    tls: bool,
+    host: String,
+    port: u16,
+    // The path and query, at least `/`.
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> io::Result<Self> {
+        let invalid = || io::Error::new(ErrorKind::InvalidInput, "Invalid URL");
+        let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+        let tls = if scheme.eq_ignore_ascii_case("http") {
+            false
+        } else if scheme.eq_ignore_ascii_case("https") {
+            true
+        } else {
+            return Err(invalid());
+        };
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, path) = match rest.find(&['/', '?'][..]) {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            let (host, port) = bracketed.split_once(']').ok_or_else(invalid)?;
+            if !host
+                .bytes()
+                .all(|b| b.is_ascii_hexdigit() || b == b':' || b == b'.')
+            {
+                return Err(invalid());
+            }
+            match port {
+                "" => (host, None),
+                port => (host, Some(port.strip_prefix(':').ok_or_else(invalid)?)),
+            }
+        } else {
+            let (host, port) = match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            };
+            if !host
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+            {
+                return Err(invalid());
+            }
+            (host, port)
+        };
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| invalid())?,
+            None if tls => 443,
+            None => 80,
+        };
+        if host.is_empty() || path.bytes().any(|b| b <= b' ' || b == 0x7F) {
+            return Err(invalid());
+        }
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        };
+        Ok(Self {
+            tls,
+            host: host.to_ascii_lowercase(),
+            port,
+            path,
+        })
+    }
+
+    // Resolve the `Location` of a redirect.
+    fn join(&self, location: &str) -> io::Result<Self> {
+        if location.contains("://") {
+            return Self::parse(location);
+        }
+        let scheme = if self.tls { "https" } else { "http" };
+        if location.starts_with("//") {
+            return Self::parse(&format!("{}:{}", scheme, location));
+        }
+        let path = if location.starts_with('/') {
+            location.to_string()
+        } else {
+            let base = self.path.split('?').next().unwrap_or_default();
+            let dir = &base[..base.rfind('/').map_or(0, |i| i + 1)];
+            format!("{}{}", dir, location)
+        };
+        Self::parse(&format!("{}://{}{}", scheme, self.authority(), path))
+    }
+
+    fn same_origin(&self, other: &Url) -> bool {
+        self.tls == other.tls && self.host == other.host && self.port == other.port
+    }
+
+    // The host and the port if not the default one, as sent in the `Host` header.
+    fn authority(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        match (self.tls, self.port) {
+            (false, 80) | (true, 443) => host,
+            (_, port) => format!("{}:{}", host, port),
+        }
+    }
+}
+
+// The `tchar` of RFC 7230.
+fn is_token(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn too_large() -> io::Error {
+    invalid_data("Response exceeds the size limit")
+}
+
+fn read_response<R: BufRead>(reader: &mut R, max_size: usize) -> io::Result<Response> {
+    let (status, reason, headers) = loop {
+        let mut budget = MAX_HEADER_SIZE;
+        let line = read_line(reader, &mut budget)?;
+        let mut parts = line.splitn(3, ' ');
+        let version = parts.next().unwrap_or_default();
+        let status = parts.next().unwrap_or_default();
+        if !version.starts_with("HTTP/1.")
+            || status.len() != 3
+            || !status.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid_data("Invalid status line"));
+        }
+        let status: u16 = status.parse().map_err(|_| invalid_data("Invalid status"))?;
+        let reason = parts.next().unwrap_or_default().to_string();
+        let headers = read_headers(reader, &mut budget)?;
+        // Interim responses such as `100 Continue` precede the final one.
+        if !(100..200).contains(&status) {
+            break (status, reason, headers);
+        }
+    };
+    let response = Response {
+        status,
+        reason,
+        headers,
+        body: Vec::new(),
+    };
+
+    let body = if status == 204 || status == 304 {
+        Vec::new()
+    } else if let Some(codings) = response.header("Transfer-Encoding") {
+        let chunked = codings
+            .rsplit(',')
+            .next()
+            .map_or(false, |c| c.trim().eq_ignore_ascii_case("chunked"));
+        if chunked {
+            read_chunked(reader, max_size)?
+        } else {
+            read_to_end(reader, max_size)?
+        }
+    } else if let Some(len) = response.header("Content-Length") {
+        let len: usize = len
+            .trim()
+            .parse()
+            .map_err(|_| invalid_data("Invalid Content-Length"))?;
+        if len > max_size {
+            return Err(too_large());
+        }
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body)?;
+        body
+    } else {
+        read_to_end(reader, max_size)?
+    };
+    Ok(Response { body, ..response })
+}
+
+// Read the header lines up to the empty line ending them.
+fn read_headers<R: BufRead>(
+    reader: &mut R,
+    budget: &mut usize,
+) -> io::Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader, budget)?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid_data("Invalid header"))?;
+        // Also rejects the obsolete line folding, which starts with a space.
+        if name.is_empty() || !name.bytes().all(is_token) {
+            return Err(invalid_data("Invalid header"));
+        }
+        headers.push((
+            name.to_string(),
+            value.trim_matches(|c| c == ' ' || c == '\t').to_string(),
+        ));
+    }
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R, max_size: usize) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut budget = MAX_CHUNK_LINE_SIZE;
+        let line = read_line(reader, &mut budget)?;
+        // Chunk extensions follow the size and are ignored.
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size =
+            usize::from_str_radix(size, 16).map_err(|_| invalid_data("Invalid chunk size"))?;
+        if size == 0 {
+            break;
+        }
+        let end = body
+            .len()
+            .checked_add(size)
+            .filter(|&end| end <= max_size)
+            .ok_or_else(too_large)?;
+        let start = body.len();
+        body.resize(end, 0);
+        reader.read_exact(&mut body[start..])?;
+        if !read_line(reader, &mut budget)?.is_empty() {
+            return Err(invalid_data("Invalid chunk"));
+        }
+    }
+    // The trailer fields are not exposed.
+    let mut budget = MAX_HEADER_SIZE;
+    read_headers(reader, &mut budget)?;
+    Ok(body)
+}
+
+fn read_to_end<R: BufRead>(reader: &mut R, max_size: usize) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    reader
+        .by_ref()
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut body)?;
+    if body.len() > max_size {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+// Read a line of at most `budget` bytes, which is reduced by the bytes read, without the line
+// ending.
+fn read_line<R: BufRead>(reader: &mut R, budget: &mut usize) -> io::Result<String> {
+    let mut line = Vec::new();
+    reader
+        .by_ref()
+        .take(*budget as u64)
+        .read_until(b'\n', &mut line)?;
+    *budget -= line.len();
+    if line.last() != Some(&b'\n') {
+        return Err(if *budget == 0 {
+            invalid_data("Response header too large")
+        } else {
+            io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of response")
+        });
+    }
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A server answering one request with a canned response, which records the request.
+    struct Peer {
+        response: Cursor<Vec<u8>>,
+        request: Vec<u8>,
+    }
+
+    impl Peer {
+        fn new(response: &str) -> Self {
+            Self {
+                response: Cursor::new(response.as_bytes().to_vec()),
+                request: Vec::new(),
+            }
+        }
+
+        fn request(&self) -> &str {
+            std::str::from_utf8(&self.request).unwrap()
+        }
+    }
+
+    impl Read for Peer {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.response.read(buf)
+        }
+    }
+
+    impl Write for Peer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.request.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Send `request` with `client`, connecting to `peers` in turn, and return the response
+    // and the URLs connected to.
+    fn send(
+        client: &Client,
+        request: &Request,
+        peers: &mut [Peer],
+    ) -> (io::Result<Response>, Vec<String>) {
+        let mut peers = peers.iter_mut();
+        let mut connected = Vec::new();
+        let response = client.send_with(request, |url| {
+            connected.push(format!("{}{}", url.authority(), url.path));
+            peers
+                .next()
+                .ok_or_else(|| io::Error::new(ErrorKind::ConnectionRefused, "No peer left"))
+        });
+        (response, connected)
+    }
+
+    fn parse(response: &str, max_size: usize) -> io::Result<Response> {
+        read_response(&mut response.as_bytes(), max_size)
+    }
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn headers(names: &[&str]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), "x".to_string()))
+            .collect()
+    }
+
+    fn names(headers: &[(String, String)]) -> Vec<&str> {
+        headers.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn content_length_body() {
+        let response = parse(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello, trailing",
+            100,
+        )
+        .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.reason(), "OK");
+        assert_eq!(response.header("content-type"), Some("text/plain"));
+        assert_eq!(response.body(), b"hello");
+
+        let error = parse("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel", 100)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn chunked_body() {
+        let response = parse(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n\
+             5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\n",
+            100,
+        )
+        .unwrap();
+        assert_eq!(response.body(), b"hello world");
+
+        let invalid = [
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nx\r\n",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nabc\r\n0\r\n\r\n",
+        ];
+        for response in invalid.iter() {
+            assert_eq!(
+                parse(response, 100).err().unwrap().kind(),
+                ErrorKind::InvalidData
+            );
+        }
+    }
+
+    #[test]
+    fn body_without_length() {
+        let response = parse("HTTP/1.0 200 OK\r\n\r\nup to the end", 100).unwrap();
+        assert_eq!(response.body(), b"up to the end");
+
+        let response = parse(
+            "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 204 No Content\r\n\r\nignored",
+            100,
+        )
+        .unwrap();
+        assert_eq!(response.status(), 204);
+        assert!(response.body().is_empty());
+    }
+
+    #[test]
+    fn size_limit() {
+        let responses = [
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nhel\r\n2\r\nlo\r\n0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n\r\nhello",
+        ];
+        for response in responses.iter() {
+            assert_eq!(parse(response, 5).unwrap().body(), b"hello");
+            assert_eq!(
+                parse(response, 4).err().unwrap().kind(),
+                ErrorKind::InvalidData
+            );
+        }
+
+        let error = parse(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\n",
+            100,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let header = format!(
+            "HTTP/1.1 200 OK\r\nX-Large: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER_SIZE)
+        );
+        assert_eq!(
+            parse(&header, 100).err().unwrap().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn redirect_changes_post_to_get() {
+        let from = url("http://example.com/form");
+        let to = url("http://example.com/done");
+        // The status, the method, and whether the request becomes a GET without body.
+        for &(status, method, rewritten) in [
+            (301, Method::Post, true),
+            (302, Method::Post, true),
+            (303, Method::Post, true),
+            (303, Method::Get, true),
+            (307, Method::Post, false),
+            (308, Method::Post, false),
+            (301, Method::Get, false),
+        ]
+        .iter()
+        {
+            let mut rewritten_method = method;
+            let mut headers = headers(&["Content-Type", "Accept"]);
+            let mut body: &[u8] = b"data";
+            redirect(
+                status,
+                &from,
+                &to,
+                &mut rewritten_method,
+                &mut headers,
+                &mut body,
+            );
+            if rewritten {
+                assert_eq!(rewritten_method, Method::Get, "{}", status);
+                assert!(body.is_empty());
+                assert_eq!(names(&headers), ["Accept"]);
+            } else {
+                assert_eq!(rewritten_method, method, "{}", status);
+                assert_eq!(body, b"data");
+                assert_eq!(names(&headers), ["Content-Type", "Accept"]);
+            }
+        }
+    }
+
+    #[test]
+    fn redirect_strips_credentials_across_origins() {
+        let from = url("http://example.com/a");
+        let sent = ["Authorization", "COOKIE", "Accept"];
+        for (to, kept) in [
+            ("http://EXAMPLE.com:80/b", &sent[..]),
+            ("http://example.org/b", &["Accept"][..]),
+            ("http://example.com:8080/b", &["Accept"][..]),
+            ("https://example.com/b", &["Accept"][..]),
+        ]
+        .iter()
+        {
+            let mut method = Method::Get;
+            let mut headers = headers(&sent);
+            let mut body: &[u8] = &[];
+            redirect(301, &from, &url(to), &mut method, &mut headers, &mut body);
+            assert_eq!(names(&headers), *kept, "{}", to);
+        }
+    }
+
+    #[test]
+    fn parse_url() {
+        let parsed = url("HTTP://Example.COM:8080/a/b?q=1#fragment");
+        assert_eq!(
+            parsed,
+            Url {
+                tls: false,
+                host: "example.com".to_string(),
+                port: 8080,
+                path: "/a/b?q=1".to_string(),
+            }
+        );
+        assert_eq!(url("https://example.com?q").path, "/?q");
+        assert_eq!(url("https://example.com").authority(), "example.com");
+
+        for invalid in [
+            "ftp://example.com/",
+            "example.com/",
+            "http:///path",
+            "http://exa mple.com/",
+            "http://example.com:http/",
+            "http://example.com/a b",
+        ]
+        .iter()
+        {
+            assert!(Url::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_ipv6_url() {
+        let parsed = url("http://[::1]:8080/path");
+        assert_eq!(parsed.host, "::1");
+        assert_eq!(parsed.port, 8080);
+        assert_eq!(parsed.path, "/path");
+        assert_eq!(parsed.authority(), "[::1]:8080");
+
+        let parsed = url("https://[FE80::1]");
+        assert_eq!(parsed.host, "fe80::1");
+        assert_eq!(parsed.port, 443);
+        assert_eq!(parsed.authority(), "[fe80::1]");
+
+        for invalid in [
+            "http://[::1/",
+            "http://[::1]8080/",
+            "http://[::g]/",
+            "http://[]/",
+        ]
+        .iter()
+        {
+            assert!(Url::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn join_url() {
+        let base = url("http://[::1]:8080/a/b?q=1");
+        assert_eq!(base.join("c").unwrap(), url("http://[::1]:8080/a/c"));
+        assert_eq!(base.join("/c?d").unwrap(), url("http://[::1]:8080/c?d"));
+        assert_eq!(base.join("//[::2]/c").unwrap(), url("http://[::2]/c"));
+        assert_eq!(
+            base.join("https://example.com/").unwrap(),
+            url("https://example.com/")
+        );
+
+        let base = url("https://example.com");
+        assert_eq!(base.join("c").unwrap(), url("https://example.com/c"));
+    }
+
+    #[test]
+    fn send_get() {
+        let mut peers = [Peer::new("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")];
+        let request = Request::get("http://Example.com:8080/a?b=c").unwrap();
+        let (response, connected) = send(&Client::new(), &request, &mut peers);
+        assert_eq!(response.unwrap().body(), b"ok");
+        assert_eq!(connected, ["example.com:8080/a?b=c"]);
+        assert_eq!(
+            peers[0].request(),
+            "GET /a?b=c HTTP/1.1\r\nHost: example.com:8080\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn send_post() {
+        let mut peers = [Peer::new("HTTP/1.1 204 No Content\r\n\r\n")];
+        let mut request = Request::post("https://[::1]/submit", b"data".to_vec()).unwrap();
+        request.set_header("Content-Type", "text/plain").unwrap();
+        let (response, _) = send(&Client::new(), &request, &mut peers);
+        assert_eq!(response.unwrap().status(), 204);
+        assert_eq!(
+            peers[0].request(),
+            "POST /submit HTTP/1.1\r\nHost: [::1]\r\nContent-Type: text/plain\r\n\
+             Content-Length: 4\r\nConnection: close\r\n\r\ndata"
+        );
+    }
+
+    #[test]
+    fn send_follows_redirects() {
+        let mut peers = [
+            Peer::new("HTTP/1.1 303 See Other\r\nLocation: /done\r\nContent-Length: 0\r\n\r\n"),
+            Peer::new("HTTP/1.1 302 Found\r\nLocation: http://other.example/final\r\n\r\n"),
+            Peer::new("HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\ndone"),
+        ];
+        let mut request = Request::post("http://example.com/form", b"data".to_vec()).unwrap();
+        request.set_header("Content-Type", "text/plain").unwrap();
+        request.set_header("Authorization", "Bearer token").unwrap();
+        let mut client = Client::new();
+        client.set_max_redirects(2);
+        let (response, connected) = send(&client, &request, &mut peers);
+        assert_eq!(response.unwrap().body(), b"done");
+        assert_eq!(
+            connected,
+            [
+                "example.com/form",
+                "example.com/done",
+                "other.example/final"
+            ]
+        );
+        assert_eq!(
+            peers[1].request(),
+            "GET /done HTTP/1.1\r\nHost: example.com\r\nAuthorization: Bearer token\r\n\
+             Connection: close\r\n\r\n"
+        );
+        assert_eq!(
+            peers[2].request(),
+            "GET /final HTTP/1.1\r\nHost: other.example\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn send_limits_redirects() {
+        let redirect = "HTTP/1.1 301 Moved Permanently\r\nLocation: /next\r\n\r\n";
+        let request = Request::get("http://example.com/").unwrap();
+
+        // Disabled by default, the redirect is the response.
+        let mut peers = [Peer::new(redirect)];
+        let (response, _) = send(&Client::new(), &request, &mut peers);
+        assert_eq!(response.unwrap().header("location"), Some("/next"));
+
+        let mut peers = [
+            Peer::new(redirect),
+            Peer::new(redirect),
+            Peer::new(redirect),
+        ];
+        let mut client = Client::new();
+        client.set_max_redirects(1);
+        let (response, connected) = send(&client, &request, &mut peers);
+        assert_eq!(response.err().unwrap().kind(), ErrorKind::Other);
+        assert_eq!(connected, ["example.com/", "example.com/next"]);
+    }
+}
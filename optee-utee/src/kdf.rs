@@ -0,0 +1,319 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Key derivation functions provided as OP-TEE extensions: HKDF (RFC 5869), the
+//! Concatenation KDF of NIST SP 800-56A and PBKDF2 (RFC 8018).
+//!
+//! Each function comes in three flavors, one returning the derived bytes, one (`*_secret`)
+//! returning them in a [Secret](../secret/struct.Secret.html) buffer wiped on drop, and one
+//! (`*_object`) returning a [TransientObject](../object/struct.TransientObject.html) of the
+//! requested type populated with the derived secret.
+
+use crate::{
+    AlgorithmId, Attribute, AttributeId, AttributeMemref, AttributeValue, DeriveKey, Error,
+    ErrorKind, Result, Secret, TransientObject, TransientObjectType,
+};
+
+/// The largest output of the derivations in bytes: the derived bytes are read from a generic
+/// secret, which OP-TEE limits to 4096 bits.
+pub const MAX_DERIVED_LEN: usize = 4096 / 8;
+
+/// Derive `out_len` bytes with HKDF.
+///
+/// # Parameters
+///
+/// 1) `algo`: One of the `Hkdf*` algorithms of [AlgorithmId](../crypto_op/enum.AlgorithmId.html),
+///    which selects the hash function.
+/// 2) `ikm`: Input keying material.
+/// 3) `salt`: Optional salt, an empty slice means no salt.
+/// 4) `info`: Optional context and application specific information, may be empty.
+/// 5) `out_len`: Length in bytes of the output keying material.
+///
+/// # Example
+///
+/// ```no_run
+/// let okm = hkdf(AlgorithmId::HkdfSha256, &ikm, b"salt", b"info", 32)?;
+/// ```
+///
+/// # Errors
+///
+/// 1) `BadParameters`: If `ikm` is empty, `out_len` is zero, greater than 255 times the
+///    digest size or greater than [MAX_DERIVED_LEN](MAX_DERIVED_LEN).
+/// 2) `NotSupported`: If the algorithm is not supported by the implementation.
+/// 3) `OutOfMemory`: If there are not enough resources to allocate the objects.
+///
+/// # Panics
+///
+/// 1) If `algo` is not one of the `Hkdf*` algorithms.
+/// 2) Hardware or cryptographic algorithm failure.
+/// 3) If the Implementation detects any other error.
+pub fn hkdf(
+    algo: AlgorithmId,
+    ikm: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    out_len: usize,
+) -> Result<Vec<u8>> {
+    hkdf_secret(algo, ikm, salt, info, out_len).map(|secret| secret.expose().clone())
+}
+
+/// Derive `out_len` bytes into a [Secret](../secret/struct.Secret.html) buffer, the parameters
+/// and errors are the same as [hkdf](fn.hkdf.html).
+pub fn hkdf_secret(
+    algo: AlgorithmId,
+    ikm: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    out_len: usize,
+) -> Result<Secret<Vec<u8>>> {
+    let mut params: Vec<Attribute> =
+        vec![AttributeValue::from_value(AttributeId::HkdfOkmLength, out_len as u32, 0).into()];
+    if !salt.is_empty() {
+        params.push(AttributeMemref::from_ref(AttributeId::HkdfSalt, salt).into());
+    }
+    if !info.is_empty() {
+        params.push(AttributeMemref::from_ref(AttributeId::HkdfInfo, info).into());
+    }
+    derive(
+        algo,
+        TransientObjectType::HkdfIkm,
+        AttributeId::HkdfIkm,
+        ikm,
+        &params,
+        out_len,
+    )
+}
+
+/// Derive a key of type `object_type` and size `key_size` in bits with HKDF.
+///
+/// The parameters and errors are the same as [hkdf](fn.hkdf.html), the length of the output
+/// keying material being `key_size` rounded up to whole bytes.
+///
+/// # Example
+///
+/// ```no_run
+/// let key = hkdf_object(AlgorithmId::HkdfSha256, &ikm, &[], b"aes key", TransientObjectType::Aes, 128)?;
+/// ```
+pub fn hkdf_object(
+    algo: AlgorithmId,
+    ikm: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    object_type: TransientObjectType,
+    key_size: usize,
+) -> Result<TransientObject> {
+    let secret = hkdf_secret(algo, ikm, salt, info, (key_size + 7) / 8)?;
+    secret_object(object_type, key_size, secret.expose())
+}
+
+/// Derive `out_len` bytes with the Concatenation KDF.
+///
+/// # Parameters
+///
+/// 1) `algo`: One of the `ConcatKdf*` algorithms of
+///    [AlgorithmId](../crypto_op/enum.AlgorithmId.html), which selects the hash function.
+/// 2) `shared_secret`: The shared secret `Z`, usually the output of a key agreement.
+/// 3) `other_info`: Optional `OtherInfo` bit string, may be empty.
+/// 4) `out_len`: Length in bytes of the derived keying material.
+///
+/// # Example
+///
+/// ```no_run
+/// let dkm = concat_kdf(AlgorithmId::ConcatKdfSha256, &z, &other_info, 16)?;
+/// ```
+///
+/// # Errors
+///
+/// 1) `BadParameters`: If `shared_secret` is empty, `out_len` is zero or greater than
+///    [MAX_DERIVED_LEN](MAX_DERIVED_LEN).
+/// 2) `NotSupported`: If the algorithm is not supported by the implementation.
+/// 3) `OutOfMemory`: If there are not enough resources to allocate the objects.
+///
+/// # Panics
+///
+/// 1) If `algo` is not one of the `ConcatKdf*` algorithms.
+/// 2) Hardware or cryptographic algorithm failure.
+/// 3) If the Implementation detects any other error.
+pub fn concat_kdf(
+    algo: AlgorithmId,
+    shared_secret: &[u8],
+    other_info: &[u8],
+    out_len: usize,
+) -> Result<Vec<u8>> {
+    concat_kdf_secret(algo, shared_secret, other_info, out_len)
+        .map(|secret| secret.expose().clone())
+}
+
+/// Derive `out_len` bytes into a [Secret](../secret/struct.Secret.html) buffer, the parameters
+/// and errors are the same as [concat_kdf](fn.concat_kdf.html).
+pub fn concat_kdf_secret(
+    algo: AlgorithmId,
+    shared_secret: &[u8],
+    other_info: &[u8],
+    out_len: usize,
+) -> Result<Secret<Vec<u8>>> {
+    let mut params: Vec<Attribute> =
+        vec![AttributeValue::from_value(AttributeId::ConcatKdfDkmLength, out_len as u32, 0).into()];
+    if !other_info.is_empty() {
+        params.push(AttributeMemref::from_ref(AttributeId::ConcatKdfOtherInfo, other_info).into());
+    }
+    derive(
+        algo,
+        TransientObjectType::ConcatKdfZ,
+        AttributeId::ConcatKdfZ,
+        shared_secret,
+        &params,
+        out_len,
+    )
+}
+
+/// Derive a key of type `object_type` and size `key_size` in bits with the Concatenation KDF.
+///
+/// The parameters and errors are the same as [concat_kdf](fn.concat_kdf.html).
+pub fn concat_kdf_object(
+    algo: AlgorithmId,
+    shared_secret: &[u8],
+    other_info: &[u8],
+    object_type: TransientObjectType,
+    key_size: usize,
+) -> Result<TransientObject> {
+    let secret = concat_kdf_secret(algo, shared_secret, other_info, (key_size + 7) / 8)?;
+    secret_object(object_type, key_size, secret.expose())
+}
+
+/// Derive `out_len` bytes from a password with PBKDF2, using HMAC-SHA1 as the pseudorandom
+/// function.
+///
+/// # Parameters
+///
+/// 1) `password`: The password to derive the key from.
+/// 2) `salt`: The salt.
+/// 3) `iterations`: The iteration count.
+/// 4) `out_len`: Length in bytes of the derived keying material.
+///
+/// # Example
+///
+/// ```no_run
+/// let dkm = pbkdf2(b"password", b"salt", 4096, 20)?;
+/// ```
+///
+/// # Errors
+///
+/// 1) `BadParameters`: If `password` is empty, `iterations` or `out_len` is zero, or `out_len`
+///    is greater than [MAX_DERIVED_LEN](MAX_DERIVED_LEN).
+/// 2) `NotSupported`: If PBKDF2 is not supported by the implementation.
+/// 3) `OutOfMemory`: If there are not enough resources to allocate the objects.
+///
+/// # Panics
+///
+/// 1) Hardware or cryptographic algorithm failure.
+/// 2) If the Implementation detects any other error.
+pub fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32, out_len: usize) -> Result<Vec<u8>> {
+    pbkdf2_secret(password, salt, iterations, out_len).map(|secret| secret.expose().clone())
+}
+
+/// Derive `out_len` bytes from a password into a [Secret](../secret/struct.Secret.html)
+/// buffer, the parameters and errors are the same as [pbkdf2](fn.pbkdf2.html).
+pub fn pbkdf2_secret(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    out_len: usize,
+) -> Result<Secret<Vec<u8>>> {
+    if iterations == 0 {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    let params: [Attribute; 3] = [
+        AttributeMemref::from_ref(AttributeId::Pbkdf2Salt, salt).into(),
+        AttributeValue::from_value(AttributeId::Pbkdf2IterationCount, iterations, 0).into(),
+        AttributeValue::from_value(AttributeId::Pbkdf2DkmLength, out_len as u32, 0).into(),
+    ];
+    derive(
+        AlgorithmId::Pbkdf2HmacSha1,
+        TransientObjectType::Pbkdf2Password,
+        AttributeId::Pbkdf2Password,
+        password,
+        &params,
+        out_len,
+    )
+}
+
+/// Derive a key of type `object_type` and size `key_size` in bits from a password with PBKDF2.
+///
+/// The parameters and errors are the same as [pbkdf2](fn.pbkdf2.html).
+///
+/// # Example
+///
+/// ```no_run
+/// let key = pbkdf2_object(b"password", b"salt", 4096, TransientObjectType::Aes, 256)?;
+/// ```
+pub fn pbkdf2_object(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    object_type: TransientObjectType,
+    key_size: usize,
+) -> Result<TransientObject> {
+    let secret = pbkdf2_secret(password, salt, iterations, (key_size + 7) / 8)?;
+    secret_object(object_type, key_size, secret.expose())
+}
+
+// Load `key` into a key object of `key_type`, run the derivation `algo` with it and read
+// the derived secret back.
+fn derive(
+    algo: AlgorithmId,
+    key_type: TransientObjectType,
+    key_id: AttributeId,
+    key: &[u8],
+    params: &[Attribute],
+    out_len: usize,
+) -> Result<Secret<Vec<u8>>> {
+    if key.is_empty() || out_len == 0 || out_len > max_out_len(algo) {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    let key_size = key.len() * 8;
+    let mut key_object = TransientObject::allocate(key_type, key_size)?;
+    key_object.populate(&[AttributeMemref::from_ref(key_id, key).into()])?;
+    let operation = DeriveKey::allocate(algo, key_size)?;
+    operation.set_key(&key_object)?;
+
+    operation.derive_secret(params, out_len)
+}
+
+// HKDF also limits its output to 255 blocks of the hash.
+fn max_out_len(algo: AlgorithmId) -> usize {
+    let hash_len = match algo {
+        AlgorithmId::HkdfMd5 => 16,
+        AlgorithmId::HkdfSha1 => 20,
+        AlgorithmId::HkdfSha224 => 28,
+        AlgorithmId::HkdfSha256 => 32,
+        AlgorithmId::HkdfSha384 => 48,
+        AlgorithmId::HkdfSha512 => 64,
+        _ => return MAX_DERIVED_LEN,
+    };
+    MAX_DERIVED_LEN.min(255 * hash_len)
+}
+
+fn secret_object(
+    object_type: TransientObjectType,
+    key_size: usize,
+    secret: &[u8],
+) -> Result<TransientObject> {
+    let mut object = TransientObject::allocate(object_type, key_size)?;
+    object.populate(&[AttributeMemref::from_ref(AttributeId::SecretValue, secret).into()])?;
+    Ok(object)
+}
@@ -0,0 +1,804 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Import and export of asymmetric keys in standard encodings.
+//!
+//! RSA and NIST curve EC keys can be loaded into a
+//! [TransientObject](../object/struct.TransientObject.html) from:
+//!
+//! 1) PKCS#1 `RSAPrivateKey` and `RSAPublicKey`,
+//! 2) PKCS#8 `PrivateKeyInfo` (unencrypted),
+//! 3) X.509 `SubjectPublicKeyInfo`,
+//! 4) SEC 1 `ECPrivateKey`,
+//! 5) JSON Web Key (RFC 7517),
+//!
+//! either DER encoded or PEM armored. Public keys can be exported back as
+//! `SubjectPublicKeyInfo`, PKCS#1 `RSAPublicKey` or JWK.
+//!
+//! # Example
+//!
+//! ```no_run
+//! let key = TransientObject::from_pem(pem)?;
+//! let operation = Asymmetric::allocate(
+//!     AlgorithmId::RsassaPkcs1V15Sha256,
+//!     OperationMode::Sign,
+//!     key.info()?.object_size(),
+//! )?;
+//! operation.set_key(&key)?;
+//! let public_key = key.public_key_to_pem(PublicKeyFormat::Spki)?;
+//! ```
+
+use crate::der::{self, Reader};
+use crate::object::buffer_attribute;
+use crate::{
+    Attribute, AttributeId, AttributeMemref, AttributeValue, Error, ErrorKind, ObjHandle, Result,
+    Secret, TransientObject, TransientObjectType,
+};
+
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+
+struct Curve {
+    id: u32,
+    bits: usize,
+    oid: &'static [u8],
+    jwk: &'static str,
+}
+
+impl Curve {
+    fn bytes(&self) -> usize {
+        (self.bits + 7) / 8
+    }
+}
+
+const CURVES: [Curve; 5] = [
+    Curve {
+        id: raw_curve::P192,
+        bits: 192,
+        oid: &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x01],
+        jwk: "P-192",
+    },
+    Curve {
+        id: raw_curve::P224,
+        bits: 224,
+        oid: &[0x2B, 0x81, 0x04, 0x00, 0x21],
+        jwk: "P-224",
+    },
+    Curve {
+        id: raw_curve::P256,
+        bits: 256,
+        oid: &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07],
+        jwk: "P-256",
+    },
+    Curve {
+        id: raw_curve::P384,
+        bits: 384,
+        oid: &[0x2B, 0x81, 0x04, 0x00, 0x22],
+        jwk: "P-384",
+    },
+    Curve {
+        id: raw_curve::P521,
+        bits: 521,
+        oid: &[0x2B, 0x81, 0x04, 0x00, 0x23],
+        jwk: "P-521",
+    },
+];
+
+mod raw_curve {
+    use optee_utee_sys as raw;
+    pub const P192: u32 = raw::TEE_ECC_CURVE_NIST_P192;
+    pub const P224: u32 = raw::TEE_ECC_CURVE_NIST_P224;
+    pub const P256: u32 = raw::TEE_ECC_CURVE_NIST_P256;
+    pub const P384: u32 = raw::TEE_ECC_CURVE_NIST_P384;
+    pub const P521: u32 = raw::TEE_ECC_CURVE_NIST_P521;
+}
+
+fn curve_by(f: impl Fn(&Curve) -> bool) -> Result<&'static Curve> {
+    CURVES
+        .iter()
+        .find(|c| f(c))
+        .ok_or_else(|| Error::new(ErrorKind::NotSupported))
+}
+
+fn bad_format() -> Error {
+    Error::new(ErrorKind::BadFormat)
+}
+
+/// Encodings of a public key for export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicKeyFormat {
+    /// X.509 `SubjectPublicKeyInfo`, PEM label `PUBLIC KEY`. Supported for all key types.
+    Spki,
+    /// PKCS#1 `RSAPublicKey`, PEM label `RSA PUBLIC KEY`. Supported for RSA keys only.
+    Pkcs1,
+}
+
+struct RsaPrivate {
+    d: Secret<Vec<u8>>,
+    // p, q, dp, dq, qi
+    crt: Option<[Secret<Vec<u8>>; 5]>,
+}
+
+enum KeyMaterial {
+    Rsa {
+        n: Vec<u8>,
+        e: Vec<u8>,
+        private: Option<RsaPrivate>,
+    },
+    Ec {
+        curve: &'static Curve,
+        x: Vec<u8>,
+        y: Vec<u8>,
+        d: Option<Secret<Vec<u8>>>,
+    },
+}
+
+impl KeyMaterial {
+    fn into_object(self, ecdh: bool) -> Result<TransientObject> {
+        match self {
+            KeyMaterial::Rsa { n, e, private } => {
+                let n = strip_zeros(&n);
+                let key_size = match n.first() {
+                    Some(first) => n.len() * 8 - first.leading_zeros() as usize,
+                    None => return Err(bad_format()),
+                };
+                let mut attrs: Vec<Attribute> = vec![
+                    AttributeMemref::from_ref(AttributeId::RsaModulus, n).into(),
+                    AttributeMemref::from_ref(AttributeId::RsaPublicExponent, &e).into(),
+                ];
+                let object_type = match &private {
+                    Some(private) => {
+                        let secret = AttributeMemref::from_secret;
+                        attrs.push(secret(AttributeId::RsaPrivateExponent, &private.d).into());
+                        if let Some([p, q, dp, dq, qi]) = &private.crt {
+                            attrs.push(secret(AttributeId::RsaPrime1, p).into());
+                            attrs.push(secret(AttributeId::RsaPrime2, q).into());
+                            attrs.push(secret(AttributeId::RsaExponent1, dp).into());
+                            attrs.push(secret(AttributeId::RsaExponent2, dq).into());
+                            attrs.push(secret(AttributeId::RsaCoefficient, qi).into());
+                        }
+                        TransientObjectType::RsaKeypair
+                    }
+                    None => TransientObjectType::RsaPublicKey,
+                };
+                let mut object = TransientObject::allocate(object_type, key_size)?;
+                object.populate(&attrs)?;
+                Ok(object)
+            }
+            KeyMaterial::Ec { curve, x, y, d } => {
+                let mut attrs: Vec<Attribute> = vec![
+                    AttributeMemref::from_ref(AttributeId::EccPublicValueX, &x).into(),
+                    AttributeMemref::from_ref(AttributeId::EccPublicValueY, &y).into(),
+                    AttributeValue::from_value(AttributeId::EccCurve, curve.id, 0).into(),
+                ];
+                if let Some(d) = &d {
+                    let d = AttributeMemref::from_secret(AttributeId::EccPrivateValue, d);
+                    attrs.push(d.into());
+                }
+                let object_type = match (ecdh, d.is_some()) {
+                    (false, true) => TransientObjectType::EcdsaKeypair,
+                    (false, false) => TransientObjectType::EcdsaPublicKey,
+                    (true, true) => TransientObjectType::EcdhKeypair,
+                    (true, false) => TransientObjectType::EcdhPublicKey,
+                };
+                let mut object = TransientObject::allocate(object_type, curve.bits)?;
+                object.populate(&attrs)?;
+                Ok(object)
+            }
+        }
+    }
+}
+
+fn strip_zeros(value: &[u8]) -> &[u8] {
+    let skip = value.iter().take_while(|b| **b == 0).count();
+    &value[skip..]
+}
+
+// Left pad a big-endian coordinate to the size of the curve.
+fn pad(value: &[u8], len: usize) -> Result<Vec<u8>> {
+    let value = strip_zeros(value);
+    if value.len() > len {
+        return Err(bad_format());
+    }
+    let mut res = vec![0u8; len - value.len()];
+    res.extend_from_slice(value);
+    Ok(res)
+}
+
+fn decode_point(curve: &'static Curve, point: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let len = curve.bytes();
+    match point.split_first() {
+        Some((0x04, xy)) if xy.len() == 2 * len => Ok((xy[..len].to_vec(), xy[len..].to_vec())),
+        // Compressed points need the curve arithmetic to recover `y`.
+        Some((0x02, _)) | Some((0x03, _)) => Err(Error::new(ErrorKind::NotSupported)),
+        _ => Err(bad_format()),
+    }
+}
+
+fn parse_pkcs1_private(der: &[u8]) -> Result<KeyMaterial> {
+    der::parse(der, |r| {
+        let mut seq = r.sequence()?;
+        // Version 1 is used for multi-prime keys, which the TEE cannot hold.
+        if seq.small_unsigned()? != 0 {
+            return Err(Error::new(ErrorKind::NotSupported));
+        }
+        let mut next = || seq.unsigned().map(|v| v.to_vec());
+        let n = next()?;
+        let e = next()?;
+        let d = Secret::new(next()?);
+        let mut secret = || next().map(Secret::new);
+        let crt = [secret()?, secret()?, secret()?, secret()?, secret()?];
+        seq.finish()?;
+        Ok(KeyMaterial::Rsa {
+            n,
+            e,
+            private: Some(RsaPrivate { d, crt: Some(crt) }),
+        })
+    })
+}
+
+fn parse_pkcs1_public(der: &[u8]) -> Result<KeyMaterial> {
+    der::parse(der, |r| {
+        let mut seq = r.sequence()?;
+        let n = seq.unsigned()?.to_vec();
+        let e = seq.unsigned()?.to_vec();
+        seq.finish()?;
+        Ok(KeyMaterial::Rsa {
+            n,
+            e,
+            private: None,
+        })
+    })
+}
+
+fn parse_sec1(der: &[u8], curve: Option<&'static Curve>) -> Result<KeyMaterial> {
+    der::parse(der, |r| {
+        let mut seq = r.sequence()?;
+        if seq.small_unsigned()? != 1 {
+            return Err(bad_format());
+        }
+        let d = Secret::new(seq.octet_string()?.to_vec());
+        let curve = match seq.optional(der::context(0))? {
+            Some(params) => {
+                let oid = der::parse(params, |p| p.oid())?;
+                curve_by(|c| c.oid == oid)?
+            }
+            None => curve.ok_or_else(bad_format)?,
+        };
+        // The TEE needs the public point, which is optional in the encoding.
+        let point = match seq.optional(der::context(1))? {
+            Some(public) => der::parse(public, |p| p.bit_string())?,
+            None => return Err(Error::new(ErrorKind::NotSupported)),
+        };
+        let (x, y) = decode_point(curve, point)?;
+        Ok(KeyMaterial::Ec {
+            curve,
+            x,
+            y,
+            d: Some(d),
+        })
+    })
+}
+
+// Parse an AlgorithmIdentifier, returning the curve for EC keys.
+fn parse_algorithm(r: &mut Reader) -> Result<Option<&'static Curve>> {
+    let mut seq = r.sequence()?;
+    let oid = seq.oid()?;
+    if oid == OID_RSA_ENCRYPTION {
+        seq.optional(der::TAG_NULL)?;
+        seq.finish()?;
+        Ok(None)
+    } else if oid == OID_EC_PUBLIC_KEY {
+        let curve_oid = seq.oid()?;
+        seq.finish()?;
+        curve_by(|c| c.oid == curve_oid).map(Some)
+    } else {
+        Err(Error::new(ErrorKind::NotSupported))
+    }
+}
+
+fn parse_spki(der: &[u8]) -> Result<KeyMaterial> {
+    der::parse(der, |r| {
+        let mut seq = r.sequence()?;
+        let curve = parse_algorithm(&mut seq)?;
+        let public = seq.bit_string()?;
+        seq.finish()?;
+        match curve {
+            None => parse_pkcs1_public(public),
+            Some(curve) => {
+                let (x, y) = decode_point(curve, public)?;
+                Ok(KeyMaterial::Ec {
+                    curve,
+                    x,
+                    y,
+                    d: None,
+                })
+            }
+        }
+    })
+}
+
+fn parse_pkcs8(der: &[u8]) -> Result<KeyMaterial> {
+    der::parse(der, |r| {
+        let mut seq = r.sequence()?;
+        if seq.small_unsigned()? > 1 {
+            return Err(bad_format());
+        }
+        let curve = parse_algorithm(&mut seq)?;
+        let private = seq.octet_string()?;
+        // Attributes and the v2 public key are not needed.
+        match curve {
+            None => parse_pkcs1_private(private),
+            Some(curve) => parse_sec1(private, Some(curve)),
+        }
+    })
+}
+
+type Parser = fn(&[u8]) -> Result<KeyMaterial>;
+
+fn parse_der(der: &[u8]) -> Result<KeyMaterial> {
+    let parsers: [Parser; 5] = [
+        parse_pkcs8,
+        parse_spki,
+        |der| parse_sec1(der, None),
+        parse_pkcs1_private,
+        parse_pkcs1_public,
+    ];
+    for parse in parsers.iter() {
+        match parse(der) {
+            Err(e) if e.kind() == ErrorKind::BadFormat => continue,
+            res => return res,
+        }
+    }
+    Err(bad_format())
+}
+
+// Members of a JSON object with a string value, other values are skipped.
+struct JsonObject(Vec<(String, String)>);
+
+impl JsonObject {
+    fn parse(json: &str) -> Result<Self> {
+        let mut parser = JsonParser {
+            bytes: json.as_bytes(),
+            pos: 0,
+        };
+        let mut members = Vec::new();
+        parser.expect(b'{')?;
+        if !parser.eat(b'}') {
+            loop {
+                let name = parser.string()?;
+                parser.expect(b':')?;
+                if parser.peek() == Some(b'"') {
+                    members.push((name, parser.string()?));
+                } else {
+                    parser.skip_value()?;
+                }
+                if parser.eat(b'}') {
+                    break;
+                }
+                parser.expect(b',')?;
+            }
+        }
+        if parser.peek().is_some() {
+            return Err(bad_format());
+        }
+        Ok(Self(members))
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn bytes(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self.get(name) {
+            Some(value) => base64::decode_config(value, base64::URL_SAFE_NO_PAD)
+                .map(Some)
+                .map_err(|_| bad_format()),
+            None => Ok(None),
+        }
+    }
+
+    fn required(&self, name: &str) -> Result<Vec<u8>> {
+        self.bytes(name)?.ok_or_else(bad_format)
+    }
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn peek(&mut self) -> Option<u8> {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(bad_format())
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect(b'"')?;
+        let mut res = String::new();
+        let mut chars = std::str::from_utf8(&self.bytes[self.pos..])
+            .map_err(|_| bad_format())?
+            .char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(res);
+                }
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'u')) => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|_| bad_format())?;
+                            // Surrogate pairs never occur in the members we read.
+                            std::char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        Some((_, c)) => c,
+                        None => return Err(bad_format()),
+                    };
+                    res.push(escaped);
+                }
+                c => res.push(c),
+            }
+        }
+        Err(bad_format())
+    }
+
+    fn skip_value(&mut self) -> Result<()> {
+        match self.peek() {
+            Some(b'"') => self.string().map(|_| ()),
+            Some(b'{') | Some(b'[') => {
+                let mut depth = 0;
+                loop {
+                    match self.peek() {
+                        Some(b'"') => {
+                            self.string()?;
+                            continue;
+                        }
+                        Some(b'{') | Some(b'[') => depth += 1,
+                        Some(b'}') | Some(b']') => depth -= 1,
+                        Some(_) => (),
+                        None => return Err(bad_format()),
+                    }
+                    self.pos += 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+            }
+            Some(_) => {
+                let len = self.bytes[self.pos..]
+                    .iter()
+                    .take_while(|c| !b",}] \t\r\n".contains(c))
+                    .count();
+                if len == 0 {
+                    return Err(bad_format());
+                }
+                self.pos += len;
+                Ok(())
+            }
+            None => Err(bad_format()),
+        }
+    }
+}
+
+fn parse_jwk(jwk: &str) -> Result<(KeyMaterial, bool)> {
+    let jwk = JsonObject::parse(jwk)?;
+    match jwk.get("kty") {
+        Some("RSA") => {
+            let private = match jwk.bytes("d")? {
+                Some(d) => {
+                    let secret = |name| jwk.required(name).map(Secret::new);
+                    let crt = match jwk.bytes("p")? {
+                        Some(p) => Some([
+                            Secret::new(p),
+                            secret("q")?,
+                            secret("dp")?,
+                            secret("dq")?,
+                            secret("qi")?,
+                        ]),
+                        None => None,
+                    };
+                    Some(RsaPrivate {
+                        d: Secret::new(d),
+                        crt,
+                    })
+                }
+                None => None,
+            };
+            let material = KeyMaterial::Rsa {
+                n: jwk.required("n")?,
+                e: jwk.required("e")?,
+                private,
+            };
+            Ok((material, false))
+        }
+        Some("EC") => {
+            let crv = jwk.get("crv").ok_or_else(bad_format)?;
+            let curve = curve_by(|c| c.jwk == crv)?;
+            let material = KeyMaterial::Ec {
+                curve,
+                x: pad(&jwk.required("x")?, curve.bytes())?,
+                y: pad(&jwk.required("y")?, curve.bytes())?,
+                d: jwk.bytes("d")?.map(Secret::new),
+            };
+            let ecdh = jwk.get("use") == Some("enc")
+                || jwk.get("alg").map_or(false, |alg| alg.starts_with("ECDH"));
+            Ok((material, ecdh))
+        }
+        Some(_) => Err(Error::new(ErrorKind::NotSupported)),
+        None => Err(bad_format()),
+    }
+}
+
+impl TransientObject {
+    /// Create a key object from a DER encoded key.
+    ///
+    /// The encoding is detected among PKCS#8 `PrivateKeyInfo`, X.509 `SubjectPublicKeyInfo`,
+    /// PKCS#1 `RSAPrivateKey` and `RSAPublicKey` and SEC 1 `ECPrivateKey`. The object type is
+    /// [RsaKeypair](TransientObjectType::RsaKeypair),
+    /// [RsaPublicKey](TransientObjectType::RsaPublicKey),
+    /// [EcdsaKeypair](TransientObjectType::EcdsaKeypair) or
+    /// [EcdsaPublicKey](TransientObjectType::EcdsaPublicKey) depending on the key, and the
+    /// object size is the size of the key. Use [from_der_ecdh](TransientObject::from_der_ecdh)
+    /// for keys meant for key agreement.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `der`: The DER encoded key.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = TransientObject::from_der(&der)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `der` is not a key in one of the supported encodings.
+    /// 2) `NotSupported`: If the key algorithm, the curve or a feature of the encoding, such as
+    ///    compressed EC points or multi-prime RSA keys, is not supported.
+    /// 3) `BadParameters`: If the TEE rejects the key material.
+    /// 4) `OutOfMemory`: If not enough resources are available to allocate the object.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        parse_der(der)?.into_object(false)
+    }
+
+    /// Same as [from_der](TransientObject::from_der), but EC keys are created as
+    /// [EcdhKeypair](TransientObjectType::EcdhKeypair) or
+    /// [EcdhPublicKey](TransientObjectType::EcdhPublicKey) objects.
+    pub fn from_der_ecdh(der: &[u8]) -> Result<Self> {
+        parse_der(der)?.into_object(true)
+    }
+
+    /// Create a key object from a PEM encoded key.
+    ///
+    /// The PEM label selects the encoding: `PRIVATE KEY` (PKCS#8), `PUBLIC KEY` (X.509
+    /// `SubjectPublicKeyInfo`), `RSA PRIVATE KEY`, `RSA PUBLIC KEY` (PKCS#1) or
+    /// `EC PRIVATE KEY` (SEC 1). The object type is chosen as in
+    /// [from_der](TransientObject::from_der).
+    ///
+    /// # Parameters
+    ///
+    /// 1) `pem`: Text containing the PEM block, anything outside the block is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let pem = "-----BEGIN PUBLIC KEY-----\n...\n-----END PUBLIC KEY-----\n";
+    /// let key = TransientObject::from_pem(pem)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `pem` holds no valid PEM block or an unknown label.
+    /// 2) `NotSupported`: If the key is encrypted, or for the reasons listed in
+    ///    [from_der](TransientObject::from_der).
+    /// 3) `BadParameters`: If the TEE rejects the key material.
+    /// 4) `OutOfMemory`: If not enough resources are available to allocate the object.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        parse_pem(pem)?.into_object(false)
+    }
+
+    /// Same as [from_pem](TransientObject::from_pem), but EC keys are created as ECDH keys.
+    pub fn from_pem_ecdh(pem: &str) -> Result<Self> {
+        parse_pem(pem)?.into_object(true)
+    }
+
+    /// Create a key object from a JSON Web Key.
+    ///
+    /// `RSA` and `EC` keys on the curves `P-192` to `P-521` are supported, a private key is
+    /// imported when `d` is present. EC keys are created as ECDH keys when `use` is `enc` or
+    /// `alg` is one of the `ECDH-ES` algorithms, as ECDSA keys otherwise.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `jwk`: The JSON object of the key.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = TransientObject::from_jwk(r#"{"kty":"EC","crv":"P-256","x":"...","y":"..."}"#)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `jwk` is not a JSON object or a required member is missing.
+    /// 2) `NotSupported`: If the key type or the curve is not supported.
+    /// 3) `BadParameters`: If the TEE rejects the key material.
+    /// 4) `OutOfMemory`: If not enough resources are available to allocate the object.
+    pub fn from_jwk(jwk: &str) -> Result<Self> {
+        let (material, ecdh) = parse_jwk(jwk)?;
+        material.into_object(ecdh)
+    }
+
+    /// Export the public part of a key object in DER.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `format`: The [PublicKeyFormat](PublicKeyFormat) of the output.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = TransientObject::allocate(TransientObjectType::RsaKeypair, 2048)?;
+    /// key.generate_key(2048, &[])?;
+    /// let spki = key.public_key_to_der(PublicKeyFormat::Spki)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If the object is not an RSA or NIST curve EC key, or `format` is not
+    ///    available for this key type.
+    /// 2) `BadState`: If the object is not initialized.
+    pub fn public_key_to_der(&self, format: PublicKeyFormat) -> Result<Vec<u8>> {
+        public_key_der(self, format)
+    }
+
+    /// Export the public part of a key object in PEM, see
+    /// [public_key_to_der](TransientObject::public_key_to_der).
+    pub fn public_key_to_pem(&self, format: PublicKeyFormat) -> Result<String> {
+        let label = match format {
+            PublicKeyFormat::Spki => "PUBLIC KEY",
+            PublicKeyFormat::Pkcs1 => "RSA PUBLIC KEY",
+        };
+        Ok(der::pem_encode(label, &self.public_key_to_der(format)?))
+    }
+
+    /// Export the public part of a key object as a JSON Web Key.
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If the object is not an RSA or NIST curve EC key.
+    /// 2) `BadState`: If the object is not initialized.
+    pub fn public_key_to_jwk(&self) -> Result<String> {
+        let b64 = |v: &[u8]| base64::encode_config(v, base64::URL_SAFE_NO_PAD);
+        match public_key_material(self)? {
+            KeyMaterial::Rsa { n, e, .. } => Ok(format!(
+                r#"{{"kty":"RSA","n":"{}","e":"{}"}}"#,
+                b64(strip_zeros(&n)),
+                b64(strip_zeros(&e))
+            )),
+            KeyMaterial::Ec { curve, x, y, .. } => Ok(format!(
+                r#"{{"kty":"EC","crv":"{}","x":"{}","y":"{}"}}"#,
+                curve.jwk,
+                b64(&x),
+                b64(&y)
+            )),
+        }
+    }
+}
+
+pub(crate) fn public_key_der<T: ObjHandle>(object: &T, format: PublicKeyFormat) -> Result<Vec<u8>> {
+    match (public_key_material(object)?, format) {
+        (KeyMaterial::Rsa { n, e, .. }, PublicKeyFormat::Pkcs1) => Ok(rsa_public_der(&n, &e)),
+        (KeyMaterial::Rsa { n, e, .. }, PublicKeyFormat::Spki) => {
+            let algorithm = der::sequence(&[&der::oid(OID_RSA_ENCRYPTION), &der::null()]);
+            let public = der::bit_string(&rsa_public_der(&n, &e));
+            Ok(der::sequence(&[&algorithm, &public]))
+        }
+        (KeyMaterial::Ec { curve, x, y, .. }, PublicKeyFormat::Spki) => {
+            let algorithm = der::sequence(&[&der::oid(OID_EC_PUBLIC_KEY), &der::oid(curve.oid)]);
+            let point = [&[0x04u8][..], &x, &y].concat();
+            Ok(der::sequence(&[&algorithm, &der::bit_string(&point)]))
+        }
+        (KeyMaterial::Ec { .. }, PublicKeyFormat::Pkcs1) => {
+            Err(Error::new(ErrorKind::NotSupported))
+        }
+    }
+}
+
+fn public_key_material<T: ObjHandle>(object: &T) -> Result<KeyMaterial> {
+    let info = object.info()?;
+    let size = (info.object_size() + 7) / 8;
+    if size == 0 {
+        return Err(Error::new(ErrorKind::BadState));
+    }
+    let read = |id: AttributeId| buffer_attribute(object, id, size);
+    let object_type = info.object_type();
+    if object_type == TransientObjectType::RsaKeypair as u32
+        || object_type == TransientObjectType::RsaPublicKey as u32
+    {
+        Ok(KeyMaterial::Rsa {
+            n: read(AttributeId::RsaModulus)?,
+            e: read(AttributeId::RsaPublicExponent)?,
+            private: None,
+        })
+    } else if object_type == TransientObjectType::EcdsaKeypair as u32
+        || object_type == TransientObjectType::EcdsaPublicKey as u32
+        || object_type == TransientObjectType::EcdhKeypair as u32
+        || object_type == TransientObjectType::EcdhPublicKey as u32
+    {
+        let (id, _) = object.value_attribute(AttributeId::EccCurve as u32)?;
+        let curve = curve_by(|c| c.id == id)?;
+        Ok(KeyMaterial::Ec {
+            curve,
+            x: pad(&read(AttributeId::EccPublicValueX)?, curve.bytes())?,
+            y: pad(&read(AttributeId::EccPublicValueY)?, curve.bytes())?,
+            d: None,
+        })
+    } else {
+        Err(Error::new(ErrorKind::NotSupported))
+    }
+}
+
+fn rsa_public_der(n: &[u8], e: &[u8]) -> Vec<u8> {
+    der::sequence(&[&der::unsigned(n), &der::unsigned(e)])
+}
+
+fn parse_pem(pem: &str) -> Result<KeyMaterial> {
+    let (label, der) = der::pem_decode(pem)?;
+    let der = Secret::new(der);
+    let der = der.expose();
+    match label.as_str() {
+        "PRIVATE KEY" => parse_pkcs8(der),
+        "PUBLIC KEY" => parse_spki(der),
+        "RSA PRIVATE KEY" => parse_pkcs1_private(der),
+        "RSA PUBLIC KEY" => parse_pkcs1_public(der),
+        "EC PRIVATE KEY" => parse_sec1(der, None),
+        "ENCRYPTED PRIVATE KEY" => Err(Error::new(ErrorKind::NotSupported)),
+        _ => Err(bad_format()),
+    }
+}
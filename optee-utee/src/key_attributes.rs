@@ -0,0 +1,761 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Typed attribute sets of the key object types.
+//!
+//! Each structure holds the attributes of one family of
+//! [TransientObjectType](../object/enum.TransientObjectType.html), so that the set passed to
+//! [TransientObject::populate](../object/struct.TransientObject.html#method.populate) is
+//! complete and consistent by construction, and can be read back from a transient or
+//! persistent object. The private values are held in [Secret](../secret/struct.Secret.html)
+//! buffers.
+//!
+//! # Example
+//!
+//! ```no_run
+//! let key = EcKeypair {
+//!     curve: ElementId::EccCurveNistP256,
+//!     x: x.to_vec(),
+//!     y: y.to_vec(),
+//!     d: Secret::new(d.to_vec()),
+//! };
+//! let object = TransientObject::from_key(TransientObjectType::EcdsaKeypair, &key)?;
+//! let public = EcPublicKey::read(&object)?;
+//! ```
+
+use crate::object::buffer_attribute;
+use crate::{
+    Attribute, AttributeId, AttributeMemref, AttributeValue, ElementId, Error, ErrorKind,
+    ObjHandle, Result, Secret, TransientObject, TransientObjectType,
+};
+
+/// The attributes of a key, for the object types listed by
+/// [accepts](KeyAttributes::accepts).
+pub trait KeyAttributes: Sized {
+    /// Return whether the attributes describe an object of `object_type`, as returned by
+    /// [ObjectInfo::object_type](../object/struct.ObjectInfo.html#method.object_type).
+    fn accepts(object_type: u32) -> bool;
+
+    /// Return whether these attributes can populate an object of `object_type`. This is
+    /// [accepts](KeyAttributes::accepts), except that the public keys only populate the public
+    /// key types, a key pair lacking its private attributes, and that the elliptic curve keys
+    /// also check that the SM2 curve is used with the SM2 object types only.
+    fn fits(&self, object_type: u32) -> bool {
+        Self::accepts(object_type)
+    }
+
+    /// Return the key size in bits, which is the `max_object_size` of the object to allocate.
+    fn key_size(&self) -> usize;
+
+    /// Return the attributes to populate an object with. The memory references borrow the
+    /// buffers of `self`.
+    fn attributes(&self) -> Vec<Attribute>;
+
+    /// Read the attributes back from an initialized object.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If the type of `object` is not [accepted](KeyAttributes::accepts).
+    /// 2) `NotSupported`: If the object holds an unknown curve.
+    ///
+    /// # Panics
+    ///
+    /// 1) If a secret attribute is read and the object usage does not contain
+    ///    [EXTRACTABLE](../object/struct.UsageFlag.html#associatedconstant.EXTRACTABLE).
+    fn read<T: ObjHandle>(object: &T) -> Result<Self>;
+}
+
+/// Parameters of the key pair generated by
+/// [TransientObject::generate_key_with](../object/struct.TransientObject.html#method.generate_key_with).
+pub trait KeyGenParams {
+    /// Return whether the parameters apply to the generation of an object of `object_type`.
+    fn accepts(object_type: u32) -> bool;
+
+    /// Return whether the parameters apply to the generation of an object of `object_type`,
+    /// see [KeyAttributes::fits](KeyAttributes::fits).
+    fn fits(&self, object_type: u32) -> bool {
+        Self::accepts(object_type)
+    }
+
+    /// Return the size in bits of the key to generate.
+    fn key_size(&self) -> usize;
+
+    /// Return the attributes to pass to
+    /// [generate_key](../object/struct.TransientObject.html#method.generate_key).
+    fn attributes(&self) -> Vec<Attribute>;
+}
+
+/// The secret value of a symmetric key: AES, DES, 3DES, SM4, HMAC and generic secrets.
+#[derive(Clone)]
+pub struct SecretKey(pub Secret<Vec<u8>>);
+
+/// An RSA public key.
+#[derive(Clone)]
+pub struct RsaPublicKey {
+    pub modulus: Vec<u8>,
+    pub public_exponent: Vec<u8>,
+}
+
+/// The CRT components of an RSA private key, all or none are given.
+#[derive(Clone)]
+pub struct RsaPrimes {
+    /// `p`
+    pub prime1: Secret<Vec<u8>>,
+    /// `q`
+    pub prime2: Secret<Vec<u8>>,
+    /// `d mod (p - 1)`
+    pub exponent1: Secret<Vec<u8>>,
+    /// `d mod (q - 1)`
+    pub exponent2: Secret<Vec<u8>>,
+    /// `q^-1 mod p`
+    pub coefficient: Secret<Vec<u8>>,
+}
+
+/// An RSA key pair.
+#[derive(Clone)]
+pub struct RsaKeypair {
+    pub modulus: Vec<u8>,
+    pub public_exponent: Vec<u8>,
+    pub private_exponent: Secret<Vec<u8>>,
+    /// The CRT components, read back as `None` when the implementation does not keep them.
+    pub primes: Option<RsaPrimes>,
+}
+
+/// The domain parameters of DSA keys.
+#[derive(Clone)]
+pub struct DsaParams {
+    pub prime: Vec<u8>,
+    pub subprime: Vec<u8>,
+    pub base: Vec<u8>,
+}
+
+/// A DSA public key.
+#[derive(Clone)]
+pub struct DsaPublicKey {
+    pub params: DsaParams,
+    pub public_value: Vec<u8>,
+}
+
+/// A DSA key pair.
+#[derive(Clone)]
+pub struct DsaKeypair {
+    pub params: DsaParams,
+    pub public_value: Vec<u8>,
+    pub private_value: Secret<Vec<u8>>,
+}
+
+/// The domain parameters of Diffie-Hellman keys.
+#[derive(Clone)]
+pub struct DhParams {
+    pub prime: Vec<u8>,
+    pub base: Vec<u8>,
+    /// The order of the subgroup generated by `base`, checked by the implementation if given.
+    pub subprime: Option<Vec<u8>>,
+    /// The size in bits of the private value to generate.
+    pub x_bits: Option<u32>,
+}
+
+/// A Diffie-Hellman key pair.
+#[derive(Clone)]
+pub struct DhKeypair {
+    pub params: DhParams,
+    pub public_value: Vec<u8>,
+    pub private_value: Secret<Vec<u8>>,
+}
+
+/// An elliptic curve public key: ECDSA, ECDH or SM2.
+#[derive(Clone)]
+pub struct EcPublicKey {
+    pub curve: ElementId,
+    pub x: Vec<u8>,
+    pub y: Vec<u8>,
+}
+
+/// An elliptic curve key pair: ECDSA, ECDH or SM2.
+#[derive(Clone)]
+pub struct EcKeypair {
+    pub curve: ElementId,
+    pub x: Vec<u8>,
+    pub y: Vec<u8>,
+    pub d: Secret<Vec<u8>>,
+}
+
+fn is_any(object_type: u32, types: &[TransientObjectType]) -> bool {
+    types.iter().any(|t| *t as u32 == object_type)
+}
+
+fn bit_len(value: &[u8]) -> usize {
+    let skip = value.iter().take_while(|b| **b == 0).count();
+    match value.get(skip) {
+        Some(first) => (value.len() - skip) * 8 - first.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+fn memref(id: AttributeId, buffer: &[u8]) -> Attribute {
+    AttributeMemref::from_ref(id, buffer).into()
+}
+
+fn secret_memref(id: AttributeId, secret: &Secret<Vec<u8>>) -> Attribute {
+    AttributeMemref::from_secret(id, secret).into()
+}
+
+// Check the type of `object` and return the byte length of its key size, which bounds the
+// length of every buffer attribute of the key types of this module.
+fn check_type<K: KeyAttributes, T: ObjHandle>(object: &T) -> Result<usize> {
+    let info = object.info()?;
+    if !K::accepts(info.object_type()) {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    Ok((info.object_size() + 7) / 8)
+}
+
+fn optional<V>(res: Result<V>) -> Result<Option<V>> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == ErrorKind::ItemNotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn curve_from_raw(id: u32) -> Result<ElementId> {
+    [
+        ElementId::EccCurveNistP192,
+        ElementId::EccCurveNistP224,
+        ElementId::EccCurveNistP256,
+        ElementId::EccCurveNistP384,
+        ElementId::EccCurveNistP521,
+        ElementId::EccCurveSm2,
+    ]
+    .iter()
+    .copied()
+    .find(|curve| *curve as u32 == id)
+    .ok_or_else(|| Error::new(ErrorKind::NotSupported))
+}
+
+fn is_sm2(object_type: u32) -> bool {
+    is_any(
+        object_type,
+        &[
+            TransientObjectType::Sm2DsaPublicKey,
+            TransientObjectType::Sm2DsaKeypair,
+            TransientObjectType::Sm2KepPublicKey,
+            TransientObjectType::Sm2KepKeypair,
+            TransientObjectType::Sm2PkePublicKey,
+            TransientObjectType::Sm2PkeKeypair,
+        ],
+    )
+}
+
+fn is_ec_public_key(object_type: u32) -> bool {
+    is_any(
+        object_type,
+        &[
+            TransientObjectType::EcdsaPublicKey,
+            TransientObjectType::EcdhPublicKey,
+            TransientObjectType::Sm2DsaPublicKey,
+            TransientObjectType::Sm2KepPublicKey,
+            TransientObjectType::Sm2PkePublicKey,
+        ],
+    )
+}
+
+// The SM2 curve is used by the SM2 object types only, which take no other curve.
+fn curve_fits(curve: ElementId, object_type: u32) -> bool {
+    (curve == ElementId::EccCurveSm2) == is_sm2(object_type)
+}
+
+// The SM2 object types imply their curve and take no curve attribute.
+fn ec_attributes(curve: ElementId, x: &[u8], y: &[u8]) -> Vec<Attribute> {
+    let mut attrs = vec![
+        memref(AttributeId::EccPublicValueX, x),
+        memref(AttributeId::EccPublicValueY, y),
+    ];
+    if curve != ElementId::EccCurveSm2 {
+        attrs.push(AttributeValue::from_curve(curve).into());
+    }
+    attrs
+}
+
+fn read_curve<T: ObjHandle>(object: &T) -> Result<ElementId> {
+    if is_sm2(object.info()?.object_type()) {
+        Ok(ElementId::EccCurveSm2)
+    } else {
+        curve_from_raw(object.value_attribute(AttributeId::EccCurve as u32)?.0)
+    }
+}
+
+impl KeyAttributes for SecretKey {
+    fn accepts(object_type: u32) -> bool {
+        is_any(
+            object_type,
+            &[
+                TransientObjectType::Aes,
+                TransientObjectType::Des,
+                TransientObjectType::Des3,
+                TransientObjectType::Sm4,
+                TransientObjectType::HmacMd5,
+                TransientObjectType::HmacSha1,
+                TransientObjectType::HmacSha224,
+                TransientObjectType::HmacSha256,
+                TransientObjectType::HmacSha384,
+                TransientObjectType::HmacSha512,
+                TransientObjectType::HmacSm3,
+                TransientObjectType::HmacSha3_224,
+                TransientObjectType::HmacSha3_256,
+                TransientObjectType::HmacSha3_384,
+                TransientObjectType::HmacSha3_512,
+                TransientObjectType::GenericSecret,
+            ],
+        )
+    }
+
+    fn key_size(&self) -> usize {
+        self.0.expose().len() * 8
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![secret_memref(AttributeId::SecretValue, &self.0)]
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        check_type::<Self, T>(object)?;
+        Ok(SecretKey(
+            object.ref_attribute_secret(AttributeId::SecretValue)?,
+        ))
+    }
+}
+
+impl KeyAttributes for RsaPublicKey {
+    fn accepts(object_type: u32) -> bool {
+        is_any(
+            object_type,
+            &[
+                TransientObjectType::RsaPublicKey,
+                TransientObjectType::RsaKeypair,
+            ],
+        )
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.modulus)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            memref(AttributeId::RsaModulus, &self.modulus),
+            memref(AttributeId::RsaPublicExponent, &self.public_exponent),
+        ]
+    }
+
+    fn fits(&self, object_type: u32) -> bool {
+        object_type == TransientObjectType::RsaPublicKey as u32
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        Ok(Self {
+            modulus: buffer_attribute(object, AttributeId::RsaModulus, size)?,
+            public_exponent: buffer_attribute(object, AttributeId::RsaPublicExponent, size)?,
+        })
+    }
+}
+
+impl KeyAttributes for RsaKeypair {
+    fn accepts(object_type: u32) -> bool {
+        object_type == TransientObjectType::RsaKeypair as u32
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.modulus)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attrs = vec![
+            memref(AttributeId::RsaModulus, &self.modulus),
+            memref(AttributeId::RsaPublicExponent, &self.public_exponent),
+            secret_memref(AttributeId::RsaPrivateExponent, &self.private_exponent),
+        ];
+        if let Some(primes) = &self.primes {
+            attrs.push(secret_memref(AttributeId::RsaPrime1, &primes.prime1));
+            attrs.push(secret_memref(AttributeId::RsaPrime2, &primes.prime2));
+            attrs.push(secret_memref(AttributeId::RsaExponent1, &primes.exponent1));
+            attrs.push(secret_memref(AttributeId::RsaExponent2, &primes.exponent2));
+            attrs.push(secret_memref(
+                AttributeId::RsaCoefficient,
+                &primes.coefficient,
+            ));
+        }
+        attrs
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        let read = |id| buffer_attribute(object, id, size);
+        let secret = |id| object.ref_attribute_secret(id);
+        let primes = match optional(secret(AttributeId::RsaPrime1))? {
+            Some(prime1) => Some(RsaPrimes {
+                prime1,
+                prime2: secret(AttributeId::RsaPrime2)?,
+                exponent1: secret(AttributeId::RsaExponent1)?,
+                exponent2: secret(AttributeId::RsaExponent2)?,
+                coefficient: secret(AttributeId::RsaCoefficient)?,
+            }),
+            None => None,
+        };
+        Ok(Self {
+            modulus: read(AttributeId::RsaModulus)?,
+            public_exponent: read(AttributeId::RsaPublicExponent)?,
+            private_exponent: secret(AttributeId::RsaPrivateExponent)?,
+            primes,
+        })
+    }
+}
+
+impl DsaParams {
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            memref(AttributeId::DsaPrime, &self.prime),
+            memref(AttributeId::DsaSubprime, &self.subprime),
+            memref(AttributeId::DsaBase, &self.base),
+        ]
+    }
+
+    fn read<T: ObjHandle>(object: &T, size: usize) -> Result<Self> {
+        Ok(Self {
+            prime: buffer_attribute(object, AttributeId::DsaPrime, size)?,
+            subprime: buffer_attribute(object, AttributeId::DsaSubprime, size)?,
+            base: buffer_attribute(object, AttributeId::DsaBase, size)?,
+        })
+    }
+}
+
+impl KeyGenParams for DsaParams {
+    fn accepts(object_type: u32) -> bool {
+        object_type == TransientObjectType::DsaKeypair as u32
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.prime)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        DsaParams::attributes(self)
+    }
+}
+
+impl KeyAttributes for DsaPublicKey {
+    fn accepts(object_type: u32) -> bool {
+        is_any(
+            object_type,
+            &[
+                TransientObjectType::DsaPublicKey,
+                TransientObjectType::DsaKeypair,
+            ],
+        )
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.params.prime)
+    }
+
+    fn fits(&self, object_type: u32) -> bool {
+        object_type == TransientObjectType::DsaPublicKey as u32
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attrs = self.params.attributes();
+        attrs.push(memref(AttributeId::DsaPublicValue, &self.public_value));
+        attrs
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        Ok(Self {
+            params: DsaParams::read(object, size)?,
+            public_value: buffer_attribute(object, AttributeId::DsaPublicValue, size)?,
+        })
+    }
+}
+
+impl KeyAttributes for DsaKeypair {
+    fn accepts(object_type: u32) -> bool {
+        object_type == TransientObjectType::DsaKeypair as u32
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.params.prime)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attrs = self.params.attributes();
+        attrs.push(memref(AttributeId::DsaPublicValue, &self.public_value));
+        attrs.push(secret_memref(
+            AttributeId::DsaPrivateValue,
+            &self.private_value,
+        ));
+        attrs
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        Ok(Self {
+            params: DsaParams::read(object, size)?,
+            public_value: buffer_attribute(object, AttributeId::DsaPublicValue, size)?,
+            private_value: object.ref_attribute_secret(AttributeId::DsaPrivateValue)?,
+        })
+    }
+}
+
+impl DhParams {
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attrs = vec![
+            memref(AttributeId::DhPrime, &self.prime),
+            memref(AttributeId::DhBase, &self.base),
+        ];
+        if let Some(subprime) = &self.subprime {
+            attrs.push(memref(AttributeId::DhSubprime, subprime));
+        }
+        if let Some(x_bits) = self.x_bits {
+            attrs.push(AttributeValue::from_value(AttributeId::DhXBits, x_bits, 0).into());
+        }
+        attrs
+    }
+
+    fn read<T: ObjHandle>(object: &T, size: usize) -> Result<Self> {
+        Ok(Self {
+            prime: buffer_attribute(object, AttributeId::DhPrime, size)?,
+            base: buffer_attribute(object, AttributeId::DhBase, size)?,
+            subprime: optional(buffer_attribute(object, AttributeId::DhSubprime, size))?,
+            x_bits: optional(object.value_attribute(AttributeId::DhXBits as u32))?
+                .map(|(x_bits, _)| x_bits),
+        })
+    }
+}
+
+impl KeyGenParams for DhParams {
+    fn accepts(object_type: u32) -> bool {
+        object_type == TransientObjectType::DhKeypair as u32
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.prime)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        DhParams::attributes(self)
+    }
+}
+
+impl KeyAttributes for DhKeypair {
+    fn accepts(object_type: u32) -> bool {
+        object_type == TransientObjectType::DhKeypair as u32
+    }
+
+    fn key_size(&self) -> usize {
+        bit_len(&self.params.prime)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attrs = self.params.attributes();
+        attrs.push(memref(AttributeId::DhPublicValue, &self.public_value));
+        attrs.push(secret_memref(
+            AttributeId::DhPrivateValue,
+            &self.private_value,
+        ));
+        attrs
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        Ok(Self {
+            params: DhParams::read(object, size)?,
+            public_value: buffer_attribute(object, AttributeId::DhPublicValue, size)?,
+            private_value: object.ref_attribute_secret(AttributeId::DhPrivateValue)?,
+        })
+    }
+}
+
+impl KeyGenParams for ElementId {
+    fn accepts(object_type: u32) -> bool {
+        EcKeypair::accepts(object_type)
+    }
+
+    fn fits(&self, object_type: u32) -> bool {
+        Self::accepts(object_type) && curve_fits(*self, object_type)
+    }
+
+    fn key_size(&self) -> usize {
+        ElementId::key_size(self)
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        if *self == ElementId::EccCurveSm2 {
+            vec![]
+        } else {
+            vec![AttributeValue::from_curve(*self).into()]
+        }
+    }
+}
+
+impl KeyAttributes for EcPublicKey {
+    fn accepts(object_type: u32) -> bool {
+        EcKeypair::accepts(object_type) || is_ec_public_key(object_type)
+    }
+
+    fn fits(&self, object_type: u32) -> bool {
+        is_ec_public_key(object_type) && curve_fits(self.curve, object_type)
+    }
+
+    fn key_size(&self) -> usize {
+        self.curve.key_size()
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        ec_attributes(self.curve, &self.x, &self.y)
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        Ok(Self {
+            curve: read_curve(object)?,
+            x: buffer_attribute(object, AttributeId::EccPublicValueX, size)?,
+            y: buffer_attribute(object, AttributeId::EccPublicValueY, size)?,
+        })
+    }
+}
+
+impl KeyAttributes for EcKeypair {
+    fn accepts(object_type: u32) -> bool {
+        is_any(
+            object_type,
+            &[
+                TransientObjectType::EcdsaKeypair,
+                TransientObjectType::EcdhKeypair,
+                TransientObjectType::Sm2DsaKeypair,
+                TransientObjectType::Sm2KepKeypair,
+                TransientObjectType::Sm2PkeKeypair,
+            ],
+        )
+    }
+
+    fn fits(&self, object_type: u32) -> bool {
+        Self::accepts(object_type) && curve_fits(self.curve, object_type)
+    }
+
+    fn key_size(&self) -> usize {
+        self.curve.key_size()
+    }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        let mut attrs = ec_attributes(self.curve, &self.x, &self.y);
+        attrs.push(secret_memref(AttributeId::EccPrivateValue, &self.d));
+        attrs
+    }
+
+    fn read<T: ObjHandle>(object: &T) -> Result<Self> {
+        let size = check_type::<Self, T>(object)?;
+        Ok(Self {
+            curve: read_curve(object)?,
+            x: buffer_attribute(object, AttributeId::EccPublicValueX, size)?,
+            y: buffer_attribute(object, AttributeId::EccPublicValueY, size)?,
+            d: object.ref_attribute_secret(AttributeId::EccPrivateValue)?,
+        })
+    }
+}
+
+impl TransientObject {
+    /// Allocate a transient object of `object_type` sized for `key` and populate it with the
+    /// attributes of `key`.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `object_type`: The type of the object, which `key` must [fit](KeyAttributes::fits):
+    ///    e.g. an [EcPublicKey](EcPublicKey) populates an `EcdsaPublicKey` object but not an
+    ///    `EcdsaKeypair` one.
+    /// 2) `key`: The key attributes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = SecretKey(Secret::new(vec![0u8; 32]));
+    /// let object = TransientObject::from_key(TransientObjectType::Aes, &key)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `key` does not [fit](KeyAttributes::fits) `object_type`, or an
+    ///    incorrect or inconsistent attribute value is detected.
+    /// 2) `OutOfMemory`: If not enough resources are available to allocate the object handle.
+    /// 3) `NotSupported`: If the key size or the object type is not supported.
+    pub fn from_key<K: KeyAttributes>(object_type: TransientObjectType, key: &K) -> Result<Self> {
+        if !key.fits(object_type as u32) {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        let mut object = Self::allocate(object_type, key.key_size())?;
+        object.populate(&key.attributes())?;
+        Ok(object)
+    }
+
+    /// Populate an uninitialized object with the attributes of `key`, see
+    /// [populate](TransientObject::populate).
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `key` does not [fit](KeyAttributes::fits) the type of this
+    ///    object, or an incorrect or inconsistent attribute value is detected.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the object is initialized.
+    /// 2) If the key is bigger than the maximum object size specified when the object was
+    ///    created.
+    pub fn populate_key<K: KeyAttributes>(&mut self, key: &K) -> Result<()> {
+        if !key.fits(self.info()?.object_type()) {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        self.populate(&key.attributes())
+    }
+
+    /// Generate a random key pair from domain parameters or a curve, see
+    /// [generate_key](TransientObject::generate_key).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let curve = ElementId::EccCurveNistP256;
+    /// let key = TransientObject::allocate(TransientObjectType::EcdhKeypair, curve.key_size())?;
+    /// key.generate_key_with(&curve)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `params` do not [fit](KeyGenParams::fits) the type of this
+    ///    object, or an incorrect or inconsistent attribute value is detected.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the object is initialized.
+    /// 2) If the key size is bigger than the maximum object size specified when the object was
+    ///    created.
+    pub fn generate_key_with<P: KeyGenParams>(&self, params: &P) -> Result<()> {
+        if !params.fits(self.info()?.object_type()) {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        self.generate_key(params.key_size(), &params.attributes())
+    }
+}
@@ -15,9 +15,13 @@
 // specific language governing permissions and limitations
 // under the License.
 
-pub use self::error::{Error, ErrorKind, Result};
+pub use self::error::{Error, ErrorKind, Result, APP_ERROR_MAX, APP_ERROR_MIN};
 pub use self::object::*;
 pub use self::crypto_op::*;
+pub use self::kdf::*;
+pub use self::key::*;
+pub use self::key_attributes::*;
+pub use self::secret::*;
 pub use self::time::*;
 pub use self::arithmetical::*;
 pub use self::extension::*;
@@ -28,14 +32,27 @@ pub use optee_utee_macros::{
 };
 
 pub mod trace;
+pub mod trace_buffer;
 #[macro_use]
 mod macros;
 mod error;
 mod parameter;
 pub mod object;
 pub mod crypto_op;
+pub mod kdf;
+pub mod key;
+pub mod key_attributes;
+pub mod secret;
+mod der;
 pub mod time;
 pub mod arithmetical;
 pub mod extension;
 pub mod uuid;
 pub mod net;
+pub mod http;
+pub mod x509;
+pub mod typestate;
+#[cfg(feature = "rustcrypto")]
+pub mod rustcrypto;
+#[cfg(feature = "tls")]
+pub mod tls;
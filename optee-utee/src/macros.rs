@@ -59,3 +59,83 @@ macro_rules! trace_println {
         $crate::trace::Trace::_print(format_args!(concat!($s, "\n"), $($tt)*));
     };
 }
+
+/// Macro for printing an error to the trace output, like `EMSG` in C.
+///
+/// The message is printed at the `TRACE_ERROR` level, with a newline, and is prefixed with
+/// the `trace_ext_prefix` of the TA and the function and line of the call. It is only printed
+/// when the current level from [Trace::get_level](trace/struct.Trace.html#method.get_level) is
+/// at least `TRACE_ERROR`, and it is compiled out when [MAX_LEVEL](trace/constant.MAX_LEVEL.html)
+/// is lower.
+///
+/// # Examples
+///
+/// ``` no_run
+/// trace_error!("invalid command {}", cmd_id);
+/// ```
+#[macro_export]
+macro_rules! trace_error {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TRACE_ERROR, $($arg)+));
+}
+
+/// Macro for printing a warning to the trace output.
+///
+/// OP-TEE has no warning level: the message is printed at the `TRACE_ERROR` level, like
+/// [trace_error](macro.trace_error.html).
+#[macro_export]
+macro_rules! trace_warn {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TRACE_ERROR, $($arg)+));
+}
+
+/// Macro for printing an informational message to the trace output, like `IMSG` in C.
+///
+/// The message is printed at the `TRACE_INFO` level, see
+/// [trace_error](macro.trace_error.html).
+#[macro_export]
+macro_rules! trace_info {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TRACE_INFO, $($arg)+));
+}
+
+/// Macro for printing a debug message to the trace output, like `DMSG` in C.
+///
+/// The message is printed at the `TRACE_DEBUG` level, see
+/// [trace_error](macro.trace_error.html).
+#[macro_export]
+macro_rules! trace_debug {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TRACE_DEBUG, $($arg)+));
+}
+
+/// Macro for printing a flow message to the trace output, like `FMSG` in C.
+///
+/// The message is printed at the `TRACE_FLOW` level, see
+/// [trace_error](macro.trace_error.html).
+#[macro_export]
+macro_rules! trace_flow {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TRACE_FLOW, $($arg)+));
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_level {
+    ($level:expr, $($arg:tt)+) => {
+        // `MAX_LEVEL` is a constant, the whole call is removed when it is lower than `$level`.
+        if $level <= $crate::trace::MAX_LEVEL && $level <= $crate::trace::Trace::get_level() {
+            $crate::trace::Trace::_print_level(
+                $level,
+                $crate::__function_name!(),
+                line!(),
+                format_args!($($arg)+),
+            );
+        }
+    };
+}
+
+// The name of the enclosing function, `__func__` in C.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __function_name {
+    () => {{
+        fn f() {}
+        $crate::trace::_function_name(f)
+    }};
+}
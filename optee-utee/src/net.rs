@@ -15,13 +15,22 @@
 // specific language governing permissions and limitations
 // under the License.
 
+//! TCP and UDP sockets over the GlobalPlatform iSocket API.
+//!
+//! Reads and writes block until they complete, unless a timeout is set with
+//! `set_read_timeout`/`set_write_timeout` or the socket is made non-blocking with
+//! `set_nonblocking`. Every return code of the iSocket API is mapped to an `io::Error`.
+
 use optee_utee_sys as raw;
+use std::ffi::CString;
 use std::io;
 use std::io::ErrorKind;
 use std::ptr;
+use std::time::Duration;
 
 pub struct TcpStream {
     pub handle: raw::TEE_iSocketHandle,
+    options: Options,
 }
 
 impl TcpStream {
@@ -30,54 +39,20 @@ impl TcpStream {
         port: u16,
         ip_version: raw::TEE_ipSocket_ipVersion,
     ) -> std::io::Result<Self> {
-        use std::ffi::CString;
-        unsafe {
-            let addr = match CString::new(address) {
-                Ok(addr) => addr,
-                Err(_) => return Err(io::Error::new(ErrorKind::Other, "Invalid address")),
-            };
-            let mut handle: raw::TEE_iSocketHandle = ptr::null_mut();
-            let mut protocol_error: u32 = 0;
-            let mut setup = raw::TEE_tcpSocket_Setup {
-                ipVersion: ip_version,
-                server_addr: addr.as_ptr() as _,
-                server_port: port,
-            };
-            let ret = ((*raw::TEE_tcpSocket).open)(
-                &mut handle,
-                &mut setup as *mut raw::TEE_tcpSocket_Setup as _,
-                &mut protocol_error,
-            );
-            match ret {
-                raw::TEE_SUCCESS => Ok(Self { handle }),
-                raw::TEE_ERROR_CANCEL => {
-                    Err(io::Error::new(ErrorKind::Interrupted, "TEE_ERROR_CANCEL"))
-                }
-                raw::TEE_ERROR_OUT_OF_MEMORY => {
-                    Err(io::Error::new(ErrorKind::Other, "TEE_ERROR_OUT_OF_MEMORY"))
-                }
-                raw::TEE_ERROR_BAD_PARAMETERS => {
-                    Err(io::Error::new(ErrorKind::Other, "TEE_ERROR_BAD_PARAMETERS"))
-                }
-                raw::TEE_ISOCKET_ERROR_TIMEOUT => Err(io::Error::new(
-                    ErrorKind::TimedOut,
-                    "TEE_ISOCKET_ERROR_TIMEOUT",
-                )),
-                raw::TEE_ERROR_COMMUNICATION => Err(io::Error::new(
-                    ErrorKind::ConnectionAborted,
-                    "TEE_ERROR_COMMUNICATION",
-                )),
-                raw::TEE_ISOCKET_ERROR_PROTOCOL => Err(io::Error::new(
-                    ErrorKind::Other,
-                    "TEE_ISOCKET_ERROR_PROTOCOL",
-                )),
-                raw::TEE_ISOCKET_WARNING_PROTOCOL => Err(io::Error::new(
-                    ErrorKind::Other,
-                    format!("TEE_ISOCKET_WARNING_PROTOCOL: {}", protocol_error),
-                )),
-                _ => panic!("Unexpected return value"),
-            }
-        }
+        let addr = match CString::new(address) {
+            Ok(addr) => addr,
+            Err(_) => return Err(io::Error::new(ErrorKind::Other, "Invalid address")),
+        };
+        let mut setup = raw::TEE_tcpSocket_Setup {
+            ipVersion: ip_version,
+            server_addr: addr.as_ptr() as _,
+            server_port: port,
+        };
+        let handle = unsafe { open(raw::TEE_tcpSocket, &mut setup)? };
+        Ok(Self {
+            handle,
+            options: Options::default(),
+        })
     }
 
     pub fn connect_v4(address: &str, port: u16) -> std::io::Result<Self> {
@@ -85,12 +60,34 @@ impl TcpStream {
     }
 
     pub fn connect_v6(address: &str, port: u16) -> std::io::Result<Self> {
-        Self::connect_with_ip_version(address, port, raw::TEE_ipSocket_ipVersion::TEE_IP_VERSION_4)
+        Self::connect_with_ip_version(address, port, raw::TEE_ipSocket_ipVersion::TEE_IP_VERSION_6)
     }
 
     pub fn connect(address: &str, port: u16) -> std::io::Result<Self> {
         Self::connect_v4(address, port)
     }
+
+    /// Set the size of the receive buffer of the socket with the `TEE_TCP_SET_RECVBUF` ioctl.
+    pub fn set_recv_buffer_size(&mut self, size: usize) -> std::io::Result<()> {
+        self.set_buffer_size(raw::TEE_TCP_SET_RECVBUF, size)
+    }
+
+    /// Set the size of the send buffer of the socket with the `TEE_TCP_SET_SENDBUF` ioctl.
+    pub fn set_send_buffer_size(&mut self, size: usize) -> std::io::Result<()> {
+        self.set_buffer_size(raw::TEE_TCP_SET_SENDBUF, size)
+    }
+
+    fn set_buffer_size(&mut self, command: u32, size: usize) -> std::io::Result<()> {
+        if size > i32::MAX as usize {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "buffer size too large",
+            ));
+        }
+        // The buffer holds a C int.
+        let mut value = (size as i32).to_ne_bytes();
+        self.ioctl(command, &mut value).map(|_| ())
+    }
 }
 
 impl Drop for TcpStream {
@@ -104,84 +101,28 @@ impl Drop for TcpStream {
 
 impl std::io::Read for TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let mut length: u32 = buf.len() as _;
-        let ret = unsafe {
-            ((*raw::TEE_tcpSocket).recv)(
+        unsafe {
+            recv(
+                raw::TEE_tcpSocket,
                 self.handle,
-                buf.as_mut_ptr() as _,
-                &mut length,
-                raw::TEE_TIMEOUT_INFINITE,
+                buf,
+                &self.options,
+                self.options.read_timeout,
             )
-        };
-
-        match ret {
-            raw::TEE_SUCCESS => Ok(length as _),
-            raw::TEE_ERROR_CANCEL => {
-                Err(io::Error::new(ErrorKind::Interrupted, "TEE_ERROR_CANCEL"))
-            }
-            raw::TEE_ISOCKET_ERROR_TIMEOUT => Err(io::Error::new(
-                ErrorKind::TimedOut,
-                "TEE_ISOCKET_ERROR_TIMEOUT",
-            )),
-            raw::TEE_ERROR_COMMUNICATION => Err(io::Error::new(
-                ErrorKind::ConnectionAborted,
-                "TEE_ERROR_COMMUNICATION",
-            )),
-            raw::TEE_ISOCKET_ERROR_REMOTE_CLOSED => Err(io::Error::new(
-                ErrorKind::ConnectionAborted,
-                "TEE_ISOCKET_ERROR_REMOTE_CLOSED",
-            )),
-            raw::TEE_ISOCKET_ERROR_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_ERROR_PROTOCOL",
-            )),
-            raw::TEE_ISOCKET_WARNING_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_WARNING_PROTOCOL",
-            )),
-            _ => panic!("Unexpected return value"),
         }
     }
 }
 
 impl std::io::Write for TcpStream {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let mut length: u32 = buf.len() as _;
-        let ret = unsafe {
-            ((*raw::TEE_tcpSocket).send)(
+        unsafe {
+            send(
+                raw::TEE_tcpSocket,
                 self.handle,
-                buf.as_ptr() as *const u8 as _,
-                &mut length,
-                raw::TEE_TIMEOUT_INFINITE,
+                buf,
+                &self.options,
+                self.options.write_timeout,
             )
-        };
-
-        match ret {
-            raw::TEE_SUCCESS => Ok(length as _),
-            raw::TEE_ERROR_CANCEL => {
-                Err(io::Error::new(ErrorKind::Interrupted, "TEE_ERROR_CANCEL"))
-            }
-            raw::TEE_ISOCKET_ERROR_TIMEOUT => Err(io::Error::new(
-                ErrorKind::TimedOut,
-                "TEE_ISOCKET_ERROR_TIMEOUT",
-            )),
-            raw::TEE_ISOCKET_ERROR_REMOTE_CLOSED => Err(io::Error::new(
-                ErrorKind::ConnectionAborted,
-                "TEE_ISOCKET_ERROR_REMOTE_CLOSED",
-            )),
-            raw::TEE_ISOCKET_ERROR_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_ERROR_PROTOCOL",
-            )),
-            raw::TEE_ISOCKET_WARNING_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_WARNING_PROTOCOL",
-            )),
-            raw::TEE_ISOCKET_ERROR_LARGE_BUFFER => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_ERROR_LARGE_BUFFER",
-            )),
-            _ => panic!("Unexpected return value"),
         }
     }
 
@@ -190,64 +131,71 @@ impl std::io::Write for TcpStream {
     }
 }
 
+/// A UDP socket.
+///
+/// The iSocket API opens UDP sockets towards one remote address, which the `TEE_UDP_CHANGEADDR`
+/// and `TEE_UDP_CHANGEPORT` ioctls can change afterwards. [send_to](UdpSocket::send_to) switches
+/// the remote address when needed, and datagrams are only received from the current one.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut socket = UdpSocket::connect("10.0.0.1", 5683)?;
+/// socket.send_to(&request, "10.0.0.2", 5683)?;
+/// let mut buf = [0u8; 512];
+/// let received = socket.recv_from_timeout(&mut buf, Duration::from_secs(2))?;
+/// if received.truncated {
+///     return Err(io::Error::new(io::ErrorKind::InvalidData, "response too large"));
+/// }
+/// ```
 pub struct UdpSocket {
     pub handle: raw::TEE_iSocketHandle,
+    options: Options,
+    peer_address: String,
+    peer_port: u16,
+    // A datagram received by peek and not consumed yet.
+    peeked: Option<Vec<u8>>,
+}
+
+/// The result of receiving one datagram with a [UdpSocket](UdpSocket).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Received {
+    /// The number of bytes written to the buffer.
+    pub len: usize,
+    /// Whether the datagram was longer than the buffer, in which case its end was discarded.
+    pub truncated: bool,
+    /// The remote address the datagram came from.
+    pub address: String,
+    /// The remote port the datagram came from.
+    pub port: u16,
 }
 
+// The largest UDP payload, over IPv4.
+const MAX_DATAGRAM_SIZE: usize = 65507;
+
 impl UdpSocket {
     fn connect_with_ip_version(
         address: &str,
         port: u16,
         ip_version: raw::TEE_ipSocket_ipVersion,
     ) -> std::io::Result<Self> {
-        use std::ffi::CString;
-        unsafe {
-            let addr = match CString::new(address) {
-                Ok(addr) => addr,
-                Err(_) => return Err(io::Error::new(ErrorKind::Other, "Invalid address")),
-            };
-            let mut handle: raw::TEE_iSocketHandle = ptr::null_mut();
-            let mut protocol_error: u32 = 0;
-            let mut setup = raw::TEE_udpSocket_Setup {
-                ipVersion: ip_version,
-                server_addr: addr.as_ptr() as _,
-                server_port: port,
-            };
-            let ret = ((*raw::TEE_udpSocket).open)(
-                &mut handle,
-                &mut setup as *mut raw::TEE_udpSocket_Setup as _,
-                &mut protocol_error,
-            );
-            match ret {
-                raw::TEE_SUCCESS => Ok(Self { handle }),
-                raw::TEE_ERROR_CANCEL => {
-                    Err(io::Error::new(ErrorKind::Interrupted, "TEE_ERROR_CANCEL"))
-                }
-                raw::TEE_ERROR_OUT_OF_MEMORY => {
-                    Err(io::Error::new(ErrorKind::Other, "TEE_ERROR_OUT_OF_MEMORY"))
-                }
-                raw::TEE_ERROR_BAD_PARAMETERS => {
-                    Err(io::Error::new(ErrorKind::Other, "TEE_ERROR_BAD_PARAMETERS"))
-                }
-                raw::TEE_ISOCKET_ERROR_TIMEOUT => Err(io::Error::new(
-                    ErrorKind::TimedOut,
-                    "TEE_ISOCKET_ERROR_TIMEOUT",
-                )),
-                raw::TEE_ERROR_COMMUNICATION => Err(io::Error::new(
-                    ErrorKind::ConnectionAborted,
-                    "TEE_ERROR_COMMUNICATION",
-                )),
-                raw::TEE_ISOCKET_ERROR_PROTOCOL => Err(io::Error::new(
-                    ErrorKind::Other,
-                    "TEE_ISOCKET_ERROR_PROTOCOL",
-                )),
-                raw::TEE_ISOCKET_WARNING_PROTOCOL => Err(io::Error::new(
-                    ErrorKind::Other,
-                    format!("TEE_ISOCKET_WARNING_PROTOCOL: {}", protocol_error),
-                )),
-                _ => panic!("Unexpected return value"),
-            }
-        }
+        let addr = match CString::new(address) {
+            Ok(addr) => addr,
+            Err(_) => return Err(io::Error::new(ErrorKind::Other, "Invalid address")),
+        };
+        let mut setup = raw::TEE_udpSocket_Setup {
+            ipVersion: ip_version,
+            server_addr: addr.as_ptr() as _,
+            server_port: port,
+        };
+        let handle = unsafe { open(raw::TEE_udpSocket, &mut setup)? };
+        Ok(Self {
+            handle,
+            options: Options::default(),
+            peer_address: address.to_string(),
+            peer_port: port,
+            peeked: None,
+        })
     }
 
     pub fn connect_v4(address: &str, port: u16) -> std::io::Result<Self> {
@@ -255,12 +203,141 @@ impl UdpSocket {
     }
 
     pub fn connect_v6(address: &str, port: u16) -> std::io::Result<Self> {
-        Self::connect_with_ip_version(address, port, raw::TEE_ipSocket_ipVersion::TEE_IP_VERSION_4)
+        Self::connect_with_ip_version(address, port, raw::TEE_ipSocket_ipVersion::TEE_IP_VERSION_6)
     }
 
     pub fn connect(address: &str, port: u16) -> std::io::Result<Self> {
         Self::connect_v4(address, port)
     }
+
+    /// Return the current remote address and port.
+    pub fn peer_addr(&self) -> (&str, u16) {
+        (&self.peer_address, self.peer_port)
+    }
+
+    /// Make `address` and `port` the remote address of the socket, for sending and receiving.
+    /// A datagram received by [peek_from](UdpSocket::peek_from) from the previous remote
+    /// address is dropped.
+    pub fn set_peer(&mut self, address: &str, port: u16) -> std::io::Result<()> {
+        if address != self.peer_address {
+            let mut addr = match CString::new(address) {
+                Ok(addr) => addr.into_bytes_with_nul(),
+                Err(_) => return Err(io::Error::new(ErrorKind::Other, "Invalid address")),
+            };
+            self.ioctl(raw::TEE_UDP_CHANGEADDR, &mut addr)?;
+            self.peer_address = address.to_string();
+            self.peeked = None;
+        }
+        if port != self.peer_port {
+            self.ioctl(raw::TEE_UDP_CHANGEPORT, &mut port.to_ne_bytes())?;
+            self.peer_port = port;
+            self.peeked = None;
+        }
+        Ok(())
+    }
+
+    /// Send `buf` as one datagram to `address` and `port`, which become the remote address of
+    /// the socket, see [set_peer](UdpSocket::set_peer). The write timeout applies.
+    pub fn send_to(&mut self, buf: &[u8], address: &str, port: u16) -> std::io::Result<usize> {
+        self.set_peer(address, port)?;
+        self.send_datagram(buf, self.options.write_timeout)
+    }
+
+    /// Same as [send_to](UdpSocket::send_to), waiting at most `timeout` for this datagram
+    /// instead of the write timeout.
+    pub fn send_to_timeout(
+        &mut self,
+        buf: &[u8],
+        address: &str,
+        port: u16,
+        timeout: Duration,
+    ) -> std::io::Result<usize> {
+        self.set_peer(address, port)?;
+        self.send_datagram(buf, checked_timeout(Some(timeout))?)
+    }
+
+    /// Receive one datagram. The read timeout applies.
+    pub fn recv_from(&mut self, buf: &mut [u8]) -> std::io::Result<Received> {
+        self.recv_datagram(buf, self.options.read_timeout, false)
+    }
+
+    /// Same as [recv_from](UdpSocket::recv_from), waiting at most `timeout` for this datagram
+    /// instead of the read timeout.
+    ///
+    /// # Errors
+    ///
+    /// 1) `TimedOut`: If no datagram is received within `timeout`.
+    /// 2) `InvalidInput`: If `timeout` is zero.
+    pub fn recv_from_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> std::io::Result<Received> {
+        self.recv_datagram(buf, checked_timeout(Some(timeout))?, false)
+    }
+
+    /// Receive one datagram without consuming it, the next receive returns it again.
+    pub fn peek_from(&mut self, buf: &mut [u8]) -> std::io::Result<Received> {
+        self.recv_datagram(buf, self.options.read_timeout, true)
+    }
+
+    /// Same as [peek_from](UdpSocket::peek_from), only returning the number of bytes written
+    /// to `buf`.
+    pub fn peek(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.peek_from(buf).map(|received| received.len)
+    }
+
+    fn send_datagram(&mut self, buf: &[u8], timeout: Option<Duration>) -> std::io::Result<usize> {
+        let len = unsafe { send(raw::TEE_udpSocket, self.handle, buf, &self.options, timeout)? };
+        if len != buf.len() {
+            return Err(io::Error::new(ErrorKind::Other, "datagram partially sent"));
+        }
+        Ok(len)
+    }
+
+    fn recv_datagram(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+        peek: bool,
+    ) -> std::io::Result<Received> {
+        let datagram = match self.peeked.take() {
+            Some(datagram) => datagram,
+            None => {
+                // One more byte than requested tells whether the datagram was truncated, a
+                // peeked datagram is received whole as later receives may use a larger buffer.
+                let size = if peek {
+                    MAX_DATAGRAM_SIZE
+                } else {
+                    buf.len() + 1
+                };
+                let mut datagram = vec![0u8; size];
+                let len = unsafe {
+                    recv(
+                        raw::TEE_udpSocket,
+                        self.handle,
+                        &mut datagram,
+                        &self.options,
+                        timeout,
+                    )?
+                };
+                datagram.truncate(len);
+                datagram
+            }
+        };
+        let len = datagram.len().min(buf.len());
+        buf[..len].copy_from_slice(&datagram[..len]);
+        let received = Received {
+            len,
+            truncated: datagram.len() > buf.len(),
+            address: self.peer_address.clone(),
+            port: self.peer_port,
+        };
+        if peek {
+            self.peeked = Some(datagram);
+        }
+        Ok(received)
+    }
 }
 
 impl Drop for UdpSocket {
@@ -273,89 +350,241 @@ impl Drop for UdpSocket {
 }
 
 impl std::io::Read for UdpSocket {
+    /// Receive one datagram, its end is discarded if it does not fit in `buf`.
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let mut length: u32 = buf.len() as _;
-        let ret = unsafe {
-            ((*raw::TEE_udpSocket).recv)(
-                self.handle,
-                buf.as_mut_ptr() as _,
-                &mut length,
-                raw::TEE_TIMEOUT_INFINITE,
-            )
-        };
-
-        match ret {
-            raw::TEE_SUCCESS => Ok(length as _),
-            raw::TEE_ERROR_CANCEL => {
-                Err(io::Error::new(ErrorKind::Interrupted, "TEE_ERROR_CANCEL"))
-            }
-            raw::TEE_ISOCKET_ERROR_TIMEOUT => Err(io::Error::new(
-                ErrorKind::TimedOut,
-                "TEE_ISOCKET_ERROR_TIMEOUT",
-            )),
-            raw::TEE_ERROR_COMMUNICATION => Err(io::Error::new(
-                ErrorKind::ConnectionAborted,
-                "TEE_ERROR_COMMUNICATION",
-            )),
-            raw::TEE_ISOCKET_ERROR_REMOTE_CLOSED => Err(io::Error::new(
-                ErrorKind::ConnectionAborted,
-                "TEE_ISOCKET_ERROR_REMOTE_CLOSED",
-            )),
-            raw::TEE_ISOCKET_ERROR_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_ERROR_PROTOCOL",
-            )),
-            raw::TEE_ISOCKET_WARNING_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_WARNING_PROTOCOL",
-            )),
-            _ => panic!("Unexpected return value"),
-        }
+        self.recv_datagram(buf, self.options.read_timeout, false)
+            .map(|received| received.len)
     }
 }
 
 impl std::io::Write for UdpSocket {
+    /// Send `buf` as one datagram to the current remote address.
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let mut length: u32 = buf.len() as _;
-        let ret = unsafe {
-            ((*raw::TEE_udpSocket).send)(
-                self.handle,
-                buf.as_ptr() as *const u8 as _,
-                &mut length,
-                raw::TEE_TIMEOUT_INFINITE,
-            )
-        };
+        self.send_datagram(buf, self.options.write_timeout)
+    }
 
-        match ret {
-            raw::TEE_SUCCESS => Ok(length as _),
-            raw::TEE_ERROR_CANCEL => {
-                Err(io::Error::new(ErrorKind::Interrupted, "TEE_ERROR_CANCEL"))
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// The methods shared by TcpStream and UdpSocket, `$interface` being the iSocket instance.
+macro_rules! socket_options {
+    ($socket:ident, $interface:expr) => {
+        impl $socket {
+            /// Set the timeout of reads, `None` to block until data is received.
+            ///
+            /// A read which times out fails with `ErrorKind::TimedOut`. The timeout has a
+            /// millisecond resolution, shorter non-zero durations are rounded up.
+            ///
+            /// # Errors
+            ///
+            /// 1) `InvalidInput`: If `timeout` is zero, use
+            ///    [set_nonblocking](Self::set_nonblocking) instead.
+            pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+                self.options.read_timeout = checked_timeout(timeout)?;
+                Ok(())
+            }
+
+            /// Return the timeout of reads, `None` if reads block until data is received.
+            pub fn read_timeout(&self) -> Option<Duration> {
+                self.options.read_timeout
+            }
+
+            /// Set the timeout of writes, `None` to block until data is sent.
+            ///
+            /// A write which times out fails with `ErrorKind::TimedOut`. The timeout has a
+            /// millisecond resolution, shorter non-zero durations are rounded up.
+            ///
+            /// # Errors
+            ///
+            /// 1) `InvalidInput`: If `timeout` is zero, use
+            ///    [set_nonblocking](Self::set_nonblocking) instead.
+            pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+                self.options.write_timeout = checked_timeout(timeout)?;
+                Ok(())
+            }
+
+            /// Return the timeout of writes, `None` if writes block until data is sent.
+            pub fn write_timeout(&self) -> Option<Duration> {
+                self.options.write_timeout
+            }
+
+            /// Make reads and writes return immediately. When nothing can be transferred
+            /// right away they fail with `ErrorKind::WouldBlock`. The timeouts are ignored
+            /// while the socket is non-blocking.
+            pub fn set_nonblocking(&mut self, nonblocking: bool) -> std::io::Result<()> {
+                self.options.nonblocking = nonblocking;
+                Ok(())
+            }
+
+            /// Send a protocol specific command to the socket through the iSocket `ioctl`.
+            ///
+            /// # Parameters
+            ///
+            /// 1) `command`: The command code, e.g. `raw::TEE_TCP_SET_RECVBUF`.
+            /// 2) `buf`: The input of the command, overwritten with its output.
+            ///
+            /// Return the length of the output written to `buf`.
+            pub fn ioctl(&mut self, command: u32, buf: &mut [u8]) -> std::io::Result<usize> {
+                unsafe { ioctl($interface, self.handle, command, buf) }
+            }
+
+            /// Return the last protocol error of the socket, as reported by the iSocket
+            /// `error` function. Its meaning depends on the protocol.
+            pub fn protocol_error(&self) -> u32 {
+                unsafe { ((*$interface).error)(self.handle) }
+            }
+        }
+    };
+}
+
+socket_options!(TcpStream, raw::TEE_tcpSocket);
+socket_options!(UdpSocket, raw::TEE_udpSocket);
+
+#[derive(Default)]
+struct Options {
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    nonblocking: bool,
+}
+
+impl Options {
+    fn timeout_millis(&self, timeout: Option<Duration>) -> u32 {
+        match (self.nonblocking, timeout) {
+            (true, _) => 0,
+            (false, None) => raw::TEE_TIMEOUT_INFINITE,
+            // Round up so that a short timeout does not become non-blocking, and stay below
+            // the value meaning infinite.
+            (false, Some(timeout)) => {
+                let millis = (timeout.as_nanos() + 999_999) / 1_000_000;
+                millis.min(raw::TEE_TIMEOUT_INFINITE as u128 - 1) as u32
             }
-            raw::TEE_ISOCKET_ERROR_TIMEOUT => Err(io::Error::new(
-                ErrorKind::TimedOut,
-                "TEE_ISOCKET_ERROR_TIMEOUT",
-            )),
-            raw::TEE_ISOCKET_ERROR_REMOTE_CLOSED => Err(io::Error::new(
-                ErrorKind::ConnectionAborted,
-                "TEE_ISOCKET_ERROR_REMOTE_CLOSED",
-            )),
-            raw::TEE_ISOCKET_ERROR_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_ERROR_PROTOCOL",
-            )),
-            raw::TEE_ISOCKET_WARNING_PROTOCOL => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_WARNING_PROTOCOL",
-            )),
-            raw::TEE_ISOCKET_ERROR_LARGE_BUFFER => Err(io::Error::new(
-                ErrorKind::Other,
-                "TEE_ISOCKET_ERROR_LARGE_BUFFER",
-            )),
-            _ => panic!("Unexpected return value"),
         }
     }
+}
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        Ok(())
+fn checked_timeout(timeout: Option<Duration>) -> std::io::Result<Option<Duration>> {
+    match timeout {
+        Some(timeout) if timeout == Duration::from_secs(0) => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "cannot set a zero duration timeout",
+        )),
+        timeout => Ok(timeout),
+    }
+}
+
+unsafe fn open<T>(
+    interface: *const raw::TEE_iSocket,
+    setup: &mut T,
+) -> std::io::Result<raw::TEE_iSocketHandle> {
+    let mut handle: raw::TEE_iSocketHandle = ptr::null_mut();
+    let mut protocol_error: u32 = 0;
+    match ((*interface).open)(&mut handle, setup as *mut T as _, &mut protocol_error) {
+        raw::TEE_SUCCESS => Ok(handle),
+        code => Err(error_from_raw(code, protocol_error, false)),
+    }
+}
+
+unsafe fn recv(
+    interface: *const raw::TEE_iSocket,
+    handle: raw::TEE_iSocketHandle,
+    buf: &mut [u8],
+    options: &Options,
+    timeout: Option<Duration>,
+) -> std::io::Result<usize> {
+    let mut length: u32 = buf.len() as _;
+    let timeout = options.timeout_millis(timeout);
+    match ((*interface).recv)(handle, buf.as_mut_ptr() as _, &mut length, timeout) {
+        raw::TEE_SUCCESS => Ok(length as _),
+        code => Err(error_from_raw(
+            code,
+            ((*interface).error)(handle),
+            options.nonblocking,
+        )),
+    }
+}
+
+unsafe fn send(
+    interface: *const raw::TEE_iSocket,
+    handle: raw::TEE_iSocketHandle,
+    buf: &[u8],
+    options: &Options,
+    timeout: Option<Duration>,
+) -> std::io::Result<usize> {
+    let mut length: u32 = buf.len() as _;
+    let timeout = options.timeout_millis(timeout);
+    match ((*interface).send)(handle, buf.as_ptr() as _, &mut length, timeout) {
+        raw::TEE_SUCCESS => Ok(length as _),
+        // Part of the data may have been sent before the timeout.
+        raw::TEE_ISOCKET_ERROR_TIMEOUT if length != 0 => Ok(length as _),
+        code => Err(error_from_raw(
+            code,
+            ((*interface).error)(handle),
+            options.nonblocking,
+        )),
+    }
+}
+
+unsafe fn ioctl(
+    interface: *const raw::TEE_iSocket,
+    handle: raw::TEE_iSocketHandle,
+    command: u32,
+    buf: &mut [u8],
+) -> std::io::Result<usize> {
+    let mut length: u32 = buf.len() as _;
+    match ((*interface).ioctl)(handle, command, buf.as_mut_ptr() as _, &mut length) {
+        raw::TEE_SUCCESS => Ok(length as _),
+        code => Err(error_from_raw(code, ((*interface).error)(handle), false)),
+    }
+}
+
+// Map a return code of the iSocket API, `protocol_error` is only reported with the protocol
+// errors and warnings.
+fn error_from_raw(code: u32, protocol_error: u32, nonblocking: bool) -> io::Error {
+    let (kind, name) = match code {
+        raw::TEE_ERROR_CANCEL => (ErrorKind::Interrupted, "TEE_ERROR_CANCEL"),
+        raw::TEE_ERROR_OUT_OF_MEMORY => (ErrorKind::Other, "TEE_ERROR_OUT_OF_MEMORY"),
+        raw::TEE_ERROR_BAD_PARAMETERS => (ErrorKind::Other, "TEE_ERROR_BAD_PARAMETERS"),
+        raw::TEE_ERROR_NOT_SUPPORTED => (ErrorKind::Other, "TEE_ERROR_NOT_SUPPORTED"),
+        raw::TEE_ERROR_COMMUNICATION => (ErrorKind::ConnectionAborted, "TEE_ERROR_COMMUNICATION"),
+        raw::TEE_ISOCKET_ERROR_TIMEOUT if nonblocking => {
+            (ErrorKind::WouldBlock, "TEE_ISOCKET_ERROR_TIMEOUT")
+        }
+        raw::TEE_ISOCKET_ERROR_TIMEOUT => (ErrorKind::TimedOut, "TEE_ISOCKET_ERROR_TIMEOUT"),
+        raw::TEE_ISOCKET_ERROR_REMOTE_CLOSED => (
+            ErrorKind::ConnectionAborted,
+            "TEE_ISOCKET_ERROR_REMOTE_CLOSED",
+        ),
+        raw::TEE_ISOCKET_ERROR_OUT_OF_RESOURCES => {
+            (ErrorKind::Other, "TEE_ISOCKET_ERROR_OUT_OF_RESOURCES")
+        }
+        raw::TEE_ISOCKET_ERROR_LARGE_BUFFER => (ErrorKind::Other, "TEE_ISOCKET_ERROR_LARGE_BUFFER"),
+        raw::TEE_ISOCKET_ERROR_HOSTNAME => (ErrorKind::Other, "TEE_ISOCKET_ERROR_HOSTNAME"),
+        raw::TEE_ISOCKET_ERROR_PROTOCOL => (ErrorKind::Other, "TEE_ISOCKET_ERROR_PROTOCOL"),
+        raw::TEE_ISOCKET_WARNING_PROTOCOL => (ErrorKind::Other, "TEE_ISOCKET_WARNING_PROTOCOL"),
+        raw::TEE_ISOCKET_TCP_WARNING_UNKNOWN_OUT_OF_BAND => (
+            ErrorKind::Other,
+            "TEE_ISOCKET_TCP_WARNING_UNKNOWN_OUT_OF_BAND",
+        ),
+        raw::TEE_ISOCKET_UDP_WARNING_UNKNOWN_OUT_OF_BAND => (
+            ErrorKind::Other,
+            "TEE_ISOCKET_UDP_WARNING_UNKNOWN_OUT_OF_BAND",
+        ),
+        code => {
+            return io::Error::new(
+                ErrorKind::Other,
+                format!("Unexpected return value: {:#010x}", code),
+            )
+        }
+    };
+    match code {
+        raw::TEE_ISOCKET_ERROR_PROTOCOL
+        | raw::TEE_ISOCKET_WARNING_PROTOCOL
+        | raw::TEE_ISOCKET_TCP_WARNING_UNKNOWN_OUT_OF_BAND
+        | raw::TEE_ISOCKET_UDP_WARNING_UNKNOWN_OUT_OF_BAND => {
+            io::Error::new(kind, format!("{}: {}", name, protocol_error))
+        }
+        _ => io::Error::new(kind, name),
     }
 }
@@ -15,7 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::{Error, Result};
+use crate::{ElementId, Error, Result, Secret, Wipe};
 use bitflags::bitflags;
 use optee_utee_sys as raw;
 use std::{marker, mem, ptr};
@@ -100,6 +100,15 @@ impl<'attrref> AttributeMemref<'attrref> {
         }
         res
     }
+
+    /// Populate a single attribute with a reference to a [Secret](../secret/struct.Secret.html)
+    /// buffer, see [from_ref](AttributeMemref::from_ref).
+    pub fn from_secret<T: Wipe + AsRef<[u8]>>(
+        id: AttributeId,
+        secret: &'attrref Secret<T>,
+    ) -> Self {
+        Self::from_ref(id, secret.expose().as_ref())
+    }
 }
 
 /// A value attribute.
@@ -142,6 +151,25 @@ impl AttributeValue {
         }
         res
     }
+
+    /// Populate an [EccCurve](AttributeId::EccCurve) attribute, which selects the curve when
+    /// generating an ECDSA or ECDH key pair.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `curve`: The [ElementId](../crypto_op/enum.ElementId.html) of the curve.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let curve = ElementId::EccCurveNistP384;
+    /// let key_size = curve.key_size();
+    /// let key = TransientObject::allocate(TransientObjectType::EcdsaKeypair, key_size)?;
+    /// key.generate_key(key_size, &[AttributeValue::from_curve(curve).into()])?;
+    /// ```
+    pub fn from_curve(curve: ElementId) -> Self {
+        Self::from_value(AttributeId::EccCurve, curve as u32, 0)
+    }
 }
 
 /// Represent the characteristics of an object.
@@ -179,15 +207,46 @@ impl ObjectInfo {
         Self { raw }
     }
 
+    /// Return the `objectType` field of the raw structrure `TEE_ObjectInfo`.
+    pub fn object_type(&self) -> u32 {
+        self.raw.objectType
+    }
+
+    /// Return the `objectType` field of the raw structrure `TEE_ObjectInfo` as a
+    /// [TransientObjectType](TransientObjectType), or `None` for a type unknown to this crate.
+    pub fn transient_type(&self) -> Option<TransientObjectType> {
+        TransientObjectType::from_raw(self.raw.objectType)
+    }
+
     /// Return the `dataSize` field of the raw structrure `TEE_ObjectInfo`.
     pub fn data_size(&self) -> usize {
         self.raw.dataSize as usize
     }
 
+    /// Return the `dataPosition` field of the raw structrure `TEE_ObjectInfo`.
+    pub fn data_position(&self) -> usize {
+        self.raw.dataPosition as usize
+    }
+
     /// Return the `objectSize` field of the raw structrure `TEE_ObjectInfo`.
     pub fn object_size(&self) -> usize {
         self.raw.objectSize as usize
     }
+
+    /// Return the `maxObjectSize` field of the raw structrure `TEE_ObjectInfo`.
+    pub fn max_object_size(&self) -> usize {
+        self.raw.maxObjectSize as usize
+    }
+
+    /// Return the `objectUsage` field of the raw structrure `TEE_ObjectInfo`.
+    pub fn object_usage(&self) -> UsageFlag {
+        UsageFlag::from_bits_truncate(self.raw.objectUsage)
+    }
+
+    /// Return the `handleFlags` field of the raw structrure `TEE_ObjectInfo`.
+    pub fn handle_flags(&self) -> HandleFlag {
+        HandleFlag::from_bits_truncate(self.raw.handleFlags)
+    }
 }
 
 /// Indicate the possible start offset when moving a data position in the data stream associated with a [PersistentObject](PersistentObject).
@@ -220,7 +279,7 @@ impl ObjectHandle {
         unsafe { *(self.raw) }
     }
 
-    fn from_raw(raw: *mut raw::TEE_ObjectHandle) -> ObjectHandle {
+    pub(crate) fn from_raw(raw: *mut raw::TEE_ObjectHandle) -> ObjectHandle {
         Self { raw }
     }
 
@@ -254,6 +313,33 @@ impl ObjectHandle {
         }
     }
 
+    fn ref_attribute_secret(&self, id: AttributeId) -> Result<Secret<Vec<u8>>> {
+        let id = id as u32;
+        // The TEE reports the size of the attribute along with a short buffer error.
+        let mut size: u32 = 0;
+        match unsafe {
+            raw::TEE_GetObjectBufferAttribute(self.handle(), id, ptr::null_mut(), &mut size)
+        } {
+            raw::TEE_SUCCESS | raw::TEE_ERROR_SHORT_BUFFER => (),
+            code => return Err(Error::from_raw_error(code)),
+        }
+        let mut secret = Secret::zeroed(size as usize);
+        match unsafe {
+            raw::TEE_GetObjectBufferAttribute(
+                self.handle(),
+                id,
+                secret.expose_mut().as_mut_ptr() as _,
+                &mut size,
+            )
+        } {
+            raw::TEE_SUCCESS => {
+                secret.truncate(size as usize);
+                Ok(secret)
+            }
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
     fn value_attribute(&self, id: u32) -> Result<(u32, u32)> {
         let mut value_a: u32 = 0;
         let mut value_b: u32 = 0;
@@ -350,7 +436,7 @@ bitflags! {
         const KEY_SET = 0x00040000;
         /// Set if the algorithm expects two keys to be set, using `TEE_SetOperationKey2`.
         /// This happens only if algorithm is set to [AesXts](../crypto_op/enum.AlgorithmId.html#variant.AesXts)
-        /// or `TEE_ALG_SM2_KEP`(not supported now).
+        /// or [Sm2Kep](../crypto_op/enum.AlgorithmId.html#variant.Sm2Kep).
         const EXPECT_TWO_KEYS = 0x00080000;
     }
 }
@@ -405,11 +491,60 @@ pub enum AttributeId {
     EccPrivateValue = 0xC0000341,
     /// ECC Curve algorithm
     EccCurve = 0xF0000441,
+    /// SM2 identity of the initiator of a key exchange
+    Sm2IdInitiator = 0xD0000446,
+    /// SM2 identity of the responder of a key exchange
+    Sm2IdResponder = 0xD0000546,
+    /// SM2 key exchange role: `a` is 0 for the initiator and 1 for the responder
+    Sm2KepUser = 0xF0000646,
+    /// SM2 key exchange confirmation value received from the peer
+    Sm2KepConfirmationIn = 0xD0000746,
+    /// SM2 key exchange confirmation value to send to the peer
+    Sm2KepConfirmationOut = 0xD0000846,
+    /// ECC ephemeral public value: `x`
+    EccEphemeralPublicValueX = 0xD0000946,
+    /// ECC ephemeral public value: `y`
+    EccEphemeralPublicValueY = 0xD0000A46,
+    /// Ed25519 pre-hash flag: set `a` to 1 to sign or verify with Ed25519ph
+    EddsaPrehash = 0xF0000004,
+    /// Ed25519 context, selects Ed25519ctx (or Ed25519ph when pre-hashing)
+    EddsaCtx = 0xD0000643,
+    /// Ed25519 public value
+    Ed25519PublicValue = 0xD0000743,
+    /// Ed25519 private value
+    Ed25519PrivateValue = 0xC0000843,
+    /// X25519 public value
+    X25519PublicValue = 0xD0000944,
+    /// X25519 private value
+    X25519PrivateValue = 0xC0000A44,
+    /// HKDF input keying material
+    HkdfIkm = 0xC00001C0,
+    /// HKDF salt
+    HkdfSalt = 0xD00002C0,
+    /// HKDF info
+    HkdfInfo = 0xD00003C0,
+    /// HKDF output length in bytes
+    HkdfOkmLength = 0xF00004C0,
+    /// Concat KDF shared secret `Z`
+    ConcatKdfZ = 0xC00001C1,
+    /// Concat KDF `OtherInfo`
+    ConcatKdfOtherInfo = 0xD00002C1,
+    /// Concat KDF derived keying material length in bytes
+    ConcatKdfDkmLength = 0xF00003C1,
+    /// PBKDF2 password
+    Pbkdf2Password = 0xC00001C2,
+    /// PBKDF2 salt
+    Pbkdf2Salt = 0xD00002C2,
+    /// PBKDF2 iteration count
+    Pbkdf2IterationCount = 0xF00003C2,
+    /// PBKDF2 derived keying material length in bytes
+    Pbkdf2DkmLength = 0xF00004C2,
     BitProtected = (1 << 28),
     BitValue = (1 << 29),
 }
 
 /// Define types of [TransientObject](TransientObject) with predefined maximum sizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum TransientObjectType {
     /// 128, 192, or 256 bits
@@ -418,6 +553,8 @@ pub enum TransientObjectType {
     Des = 0xA0000011,
     /// 128 or 192 bits including the parity bits. This gives effective key sizes of 112 or 168 bits
     Des3 = 0xA0000013,
+    /// Always 128 bits
+    Sm4 = 0xA0000014,
     /// Between 64 and 512 bits, multiple of 8 bits
     HmacMd5 = 0xA0000001,
     /// Between 80 and 512 bits, multiple of 8 bits
@@ -430,6 +567,16 @@ pub enum TransientObjectType {
     HmacSha384 = 0xA0000005,
     /// Between 256 and 1024 bits, multiple of 8 bits
     HmacSha512 = 0xA0000006,
+    /// Between 80 and 1024 bits, multiple of 8 bits
+    HmacSm3 = 0xA0000007,
+    /// Between 192 and 1024 bits, multiple of 8 bits
+    HmacSha3_224 = 0xA0000008,
+    /// Between 256 and 1024 bits, multiple of 8 bits
+    HmacSha3_256 = 0xA0000009,
+    /// Between 256 and 1024 bits, multiple of 8 bits
+    HmacSha3_384 = 0xA000000A,
+    /// Between 256 and 1024 bits, multiple of 8 bits
+    HmacSha3_512 = 0xA000000B,
     /// The number of bits in the modulus. 256, 512, 768, 1024, 1536 and 2048 bit keys SHALL be supported.
     /// Support for other key sizes including bigger key sizes is
     /// implementation-dependent. Minimum key size is 256 bits
@@ -462,19 +609,136 @@ pub enum TransientObjectType {
     /// one of the ECC curves defined in Table 6-14 with "generic" equal to
     /// "Y" is supported. SHALL be same value as for ECDH public key size
     EcdhKeypair = 0xA1000042,
+    /// 256 bits.
+    Ed25519PublicKey = 0xA0000043,
+    /// 256 bits.
+    Ed25519Keypair = 0xA1000043,
+    /// 256 bits.
+    X25519PublicKey = 0xA0000044,
+    /// 256 bits.
+    X25519Keypair = 0xA1000044,
+    /// 256 bits.
+    Sm2DsaPublicKey = 0xA0000045,
+    /// 256 bits.
+    Sm2DsaKeypair = 0xA1000045,
+    /// 256 bits.
+    Sm2KepPublicKey = 0xA0000046,
+    /// 256 bits.
+    Sm2KepKeypair = 0xA1000046,
+    /// 256 bits.
+    Sm2PkePublicKey = 0xA0000047,
+    /// 256 bits.
+    Sm2PkeKeypair = 0xA1000047,
     /// Multiple of 8 bits, up to 4096 bits. This type is intended for secret
     /// data that has been derived from a key derivation scheme.
     GenericSecret = 0xA0000000,
+    /// Input keying material of the `Hkdf*` algorithms, multiple of 8 bits.
+    HkdfIkm = 0xA10000C0,
+    /// Shared secret `Z` of the `ConcatKdf*` algorithms, multiple of 8 bits.
+    ConcatKdfZ = 0xA10000C1,
+    /// Password of the [Pbkdf2HmacSha1](../crypto_op/enum.AlgorithmId.html#variant.Pbkdf2HmacSha1)
+    /// algorithm, multiple of 8 bits.
+    Pbkdf2Password = 0xA10000C2,
     /// Object is corrupted.
     CorruptedObject = 0xA00000BE,
     /// 0 – All data is in the associated data stream.
     Data = 0xA00000BF,
 }
 
+impl TransientObjectType {
+    /// Return the object type identified by `raw`, or `None` if it is not a known type.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0xA0000010 => Some(TransientObjectType::Aes),
+            0xA0000011 => Some(TransientObjectType::Des),
+            0xA0000013 => Some(TransientObjectType::Des3),
+            0xA0000014 => Some(TransientObjectType::Sm4),
+            0xA0000001 => Some(TransientObjectType::HmacMd5),
+            0xA0000002 => Some(TransientObjectType::HmacSha1),
+            0xA0000003 => Some(TransientObjectType::HmacSha224),
+            0xA0000004 => Some(TransientObjectType::HmacSha256),
+            0xA0000005 => Some(TransientObjectType::HmacSha384),
+            0xA0000006 => Some(TransientObjectType::HmacSha512),
+            0xA0000007 => Some(TransientObjectType::HmacSm3),
+            0xA0000008 => Some(TransientObjectType::HmacSha3_224),
+            0xA0000009 => Some(TransientObjectType::HmacSha3_256),
+            0xA000000A => Some(TransientObjectType::HmacSha3_384),
+            0xA000000B => Some(TransientObjectType::HmacSha3_512),
+            0xA0000030 => Some(TransientObjectType::RsaPublicKey),
+            0xA1000030 => Some(TransientObjectType::RsaKeypair),
+            0xA0000031 => Some(TransientObjectType::DsaPublicKey),
+            0xA1000031 => Some(TransientObjectType::DsaKeypair),
+            0xA1000032 => Some(TransientObjectType::DhKeypair),
+            0xA0000041 => Some(TransientObjectType::EcdsaPublicKey),
+            0xA1000041 => Some(TransientObjectType::EcdsaKeypair),
+            0xA0000042 => Some(TransientObjectType::EcdhPublicKey),
+            0xA1000042 => Some(TransientObjectType::EcdhKeypair),
+            0xA0000043 => Some(TransientObjectType::Ed25519PublicKey),
+            0xA1000043 => Some(TransientObjectType::Ed25519Keypair),
+            0xA0000044 => Some(TransientObjectType::X25519PublicKey),
+            0xA1000044 => Some(TransientObjectType::X25519Keypair),
+            0xA0000045 => Some(TransientObjectType::Sm2DsaPublicKey),
+            0xA1000045 => Some(TransientObjectType::Sm2DsaKeypair),
+            0xA0000046 => Some(TransientObjectType::Sm2KepPublicKey),
+            0xA1000046 => Some(TransientObjectType::Sm2KepKeypair),
+            0xA0000047 => Some(TransientObjectType::Sm2PkePublicKey),
+            0xA1000047 => Some(TransientObjectType::Sm2PkeKeypair),
+            0xA0000000 => Some(TransientObjectType::GenericSecret),
+            0xA10000C0 => Some(TransientObjectType::HkdfIkm),
+            0xA10000C1 => Some(TransientObjectType::ConcatKdfZ),
+            0xA10000C2 => Some(TransientObjectType::Pbkdf2Password),
+            0xA00000BE => Some(TransientObjectType::CorruptedObject),
+            0xA00000BF => Some(TransientObjectType::Data),
+            _ => None,
+        }
+    }
+}
+
 /// A trait for an object (trasient or persistent) to return its handle.
+///
+/// The provided methods are the accessors shared by every object kind, so that generic code
+/// reads transient and persistent objects alike.
 pub trait ObjHandle {
     /// Return the handle of an object.
     fn handle(&self) -> raw::TEE_ObjectHandle;
+
+    /// Same as [TransientObject::info](TransientObject::info).
+    fn info(&self) -> Result<ObjectInfo> {
+        with_handle(self, |handle| handle.info())
+    }
+
+    /// Same as [TransientObject::ref_attribute](TransientObject::ref_attribute).
+    fn ref_attribute(&self, id: AttributeId, buffer: &mut [u8]) -> Result<usize> {
+        with_handle(self, |handle| handle.ref_attribute(id, buffer))
+    }
+
+    /// Same as [TransientObject::ref_attribute_secret](TransientObject::ref_attribute_secret).
+    fn ref_attribute_secret(&self, id: AttributeId) -> Result<Secret<Vec<u8>>> {
+        with_handle(self, |handle| handle.ref_attribute_secret(id))
+    }
+
+    /// Same as [TransientObject::value_attribute](TransientObject::value_attribute).
+    fn value_attribute(&self, id: u32) -> Result<(u32, u32)> {
+        with_handle(self, |handle| handle.value_attribute(id))
+    }
+}
+
+// Read the buffer attribute `id` of an object, of at most `size` bytes.
+pub(crate) fn buffer_attribute<T: ObjHandle>(
+    object: &T,
+    id: AttributeId,
+    size: usize,
+) -> Result<Vec<u8>> {
+    let mut buffer = vec![0u8; size];
+    let len = object.ref_attribute(id, &mut buffer)?;
+    buffer.truncate(len);
+    Ok(buffer)
+}
+
+// Run `f` on an `ObjectHandle` borrowing the handle of `object`.
+fn with_handle<T: ObjHandle + ?Sized, R>(object: &T, f: impl FnOnce(&ObjectHandle) -> R) -> R {
+    let mut handle = object.handle();
+    f(&ObjectHandle::from_raw(&mut handle))
 }
 
 /// An object containing attributes but no data stream, which is reclaimed
@@ -689,6 +953,30 @@ impl TransientObject {
         self.0.ref_attribute(id, buffer)
     }
 
+    /// Extract one buffer attribute from an object into a [Secret](../secret/struct.Secret.html)
+    /// buffer of the size of the attribute, which is wiped when dropped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = object.ref_attribute_secret(AttributeId::SecretValue)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `ItemNotFound`: If the attribute is not found on this object.
+    ///
+    /// # Panics
+    ///
+    /// 1) If object is not a valid opened object.
+    /// 2) If the object is not initialized.
+    /// 3) If the Attribute is not a buffer attribute.
+    /// 4) If the attribute is protected and the object usage does not contain
+    ///    [EXTRACTABLE](UsageFlag::EXTRACTABLE).
+    pub fn ref_attribute_secret(&self, id: AttributeId) -> Result<Secret<Vec<u8>>> {
+        self.0.ref_attribute_secret(id)
+    }
+
     /// Extract one value attribute from an object. The attribute is identified by the argument id.
     ///
     /// # Parameters
@@ -1142,6 +1430,20 @@ impl PersistentObject {
         self.0.ref_attribute(id, buffer)
     }
 
+    /// Extract one buffer attribute from an object into a [Secret](../secret/struct.Secret.html)
+    /// buffer. Function is similar to
+    /// [TransientObject::ref_attribute_secret](TransientObject::ref_attribute_secret) besides
+    /// extra errors.
+    ///
+    /// # Errors
+    ///
+    /// 1) `CorruptObject`: If the [PersistentObject](PersistentObject) is corrupt. The object handle is closed.
+    /// 2) `StorageNotAvailable`: If the [PersistentObject](PersistentObject) is stored in a storage area which is
+    ///    currently inaccessible.
+    pub fn ref_attribute_secret(&self, id: AttributeId) -> Result<Secret<Vec<u8>>> {
+        self.0.ref_attribute_secret(id)
+    }
+
     /// Extract one value attribute from an object. The attribute is identified by the argument id.
     /// Function is similar to [TransientObject::value_attribute](TransientObject::value_attribute) besides extra errors.
     ///
@@ -0,0 +1,478 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! [RustCrypto](https://github.com/RustCrypto) trait implementations backed by the TEE
+//! cryptographic operations.
+//!
+//! Third-party crates written against the `digest`, `crypto-mac`, `cipher` and `signature`
+//! traits can use the types in this module to run on the TEE crypto implementation instead
+//! of a software fallback. This module is only available with the `rustcrypto` feature.
+//!
+//! The RustCrypto traits fix output, key and block sizes at the type level, so every
+//! supported algorithm is exposed as its own type, e.g. [Sha256](Sha256) or
+//! [HmacSha256](HmacSha256). The raw [Digest](../crypto_op/struct.Digest.html) operation
+//! additionally implements `digest::Update` and `digest::Reset`, and
+//! [Asymmetric](../crypto_op/struct.Asymmetric.html) implements `signature::Signer` and
+//! `signature::Verifier` for the key programmed into it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use optee_utee::rustcrypto::Sha256;
+//! use digest::Digest;
+//!
+//! let hash = Sha256::digest(b"hello world");
+//! ```
+
+use crate::{
+    AlgorithmId, Asymmetric, AttributeId, AttributeMemref, Digest, Error, ErrorKind, Mac,
+    OperationMode, Result, TransientObject, TransientObjectType,
+};
+use cipher::consts::{
+    U1, U104, U128, U136, U144, U16, U20, U24, U28, U32, U48, U64, U72, U8,
+};
+use cipher::generic_array::GenericArray;
+use std::fmt;
+
+impl digest::Update for Digest {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        Digest::update(self, data.as_ref())
+    }
+}
+
+impl digest::Reset for Digest {
+    fn reset(&mut self) {
+        Digest::reset(self)
+    }
+}
+
+macro_rules! tee_digest {
+    ($name:ident, $algo:ident, $output:ty, $doc:expr) => {
+        #[doc = $doc]
+        ///
+        /// Implements `digest::Digest` through the `Update`, `FixedOutput`, `Reset`, `Clone`
+        /// and `Default` traits.
+        ///
+        /// # Panics
+        ///
+        /// 1) If the operation cannot be allocated when the hasher is created or cloned.
+        pub struct $name(Digest);
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self(
+                    Digest::allocate(AlgorithmId::$algo)
+                        .expect(concat!("failed to allocate ", stringify!($name), " operation")),
+                )
+            }
+        }
+
+        impl Clone for $name {
+            fn clone(&self) -> Self {
+                let mut res = Self::default();
+                res.0.copy(&self.0);
+                res
+            }
+        }
+
+        impl digest::Update for $name {
+            fn update(&mut self, data: impl AsRef<[u8]>) {
+                self.0.update(data.as_ref());
+            }
+        }
+
+        impl digest::FixedOutput for $name {
+            type OutputSize = $output;
+
+            fn finalize_into(mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+                self.finalize_into_reset(out);
+            }
+
+            // The TEE moves the operation back to the initial state after `do_final`.
+            fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+                self.0
+                    .do_final(&[], out)
+                    .expect("digest output buffer matches the algorithm");
+            }
+        }
+
+        impl digest::Reset for $name {
+            fn reset(&mut self) {
+                self.0.reset();
+            }
+        }
+    };
+}
+
+tee_digest!(Md5, Md5, U16, "MD5 hasher backed by the TEE.");
+tee_digest!(Sha1, Sha1, U20, "SHA-1 hasher backed by the TEE.");
+tee_digest!(Sha224, Sha224, U28, "SHA-224 hasher backed by the TEE.");
+tee_digest!(Sha256, Sha256, U32, "SHA-256 hasher backed by the TEE.");
+tee_digest!(Sha384, Sha384, U48, "SHA-384 hasher backed by the TEE.");
+tee_digest!(Sha512, Sha512, U64, "SHA-512 hasher backed by the TEE.");
+tee_digest!(Sha3_224, Sha3_224, U28, "SHA3-224 hasher backed by the TEE.");
+tee_digest!(Sha3_256, Sha3_256, U32, "SHA3-256 hasher backed by the TEE.");
+tee_digest!(Sha3_384, Sha3_384, U48, "SHA3-384 hasher backed by the TEE.");
+tee_digest!(Sha3_512, Sha3_512, U64, "SHA3-512 hasher backed by the TEE.");
+
+// Load `key` into a freshly allocated transient object of the given type.
+fn secret_key(object_type: TransientObjectType, key: &[u8]) -> Result<TransientObject> {
+    let mut object = TransientObject::allocate(object_type, key.len() * 8)?;
+    let attr = AttributeMemref::from_ref(AttributeId::SecretValue, key);
+    object.populate(&[attr.into()])?;
+    Ok(object)
+}
+
+macro_rules! tee_mac {
+    ($name:ident, $algo:ident, $key_size:ty, $output:ty, $doc:expr) => {
+        #[doc = $doc]
+        ///
+        /// Implements `crypto_mac::Mac` and `crypto_mac::NewMac`. The key length is checked by
+        /// the TEE, so `new_from_slice` accepts any length valid for the algorithm.
+        ///
+        /// # Panics
+        ///
+        /// 1) If `new` is called with a key rejected by the TEE.
+        /// 2) If the operation cannot be allocated when the instance is cloned.
+        pub struct $name {
+            op: Mac,
+            key_size: usize,
+        }
+
+        impl crypto_mac::NewMac for $name {
+            type KeySize = $key_size;
+
+            fn new(key: &crypto_mac::Key<Self>) -> Self {
+                Self::new_from_slice(key).expect(concat!("invalid ", stringify!($name), " key"))
+            }
+
+            fn new_from_slice(key: &[u8]) -> std::result::Result<Self, crypto_mac::InvalidKeyLength> {
+                let op = secret_key(TransientObjectType::$algo, key)
+                    .and_then(|object| {
+                        let op = Mac::allocate(AlgorithmId::$algo, key.len() * 8)?;
+                        op.set_key(&object)?;
+                        Ok(op)
+                    })
+                    .map_err(|_| crypto_mac::InvalidKeyLength)?;
+                op.init(&[]);
+                Ok(Self {
+                    op,
+                    key_size: key.len() * 8,
+                })
+            }
+        }
+
+        impl Clone for $name {
+            fn clone(&self) -> Self {
+                let mut op = Mac::allocate(AlgorithmId::$algo, self.key_size)
+                    .expect(concat!("failed to allocate ", stringify!($name), " operation"));
+                op.copy(&self.op);
+                Self {
+                    op,
+                    key_size: self.key_size,
+                }
+            }
+        }
+
+        impl crypto_mac::Mac for $name {
+            type OutputSize = $output;
+
+            fn update(&mut self, data: &[u8]) {
+                self.op.update(data);
+            }
+
+            fn reset(&mut self) {
+                self.op.reset();
+                self.op.init(&[]);
+            }
+
+            fn finalize(mut self) -> crypto_mac::Output<Self> {
+                self.finalize_reset()
+            }
+
+            fn finalize_reset(&mut self) -> crypto_mac::Output<Self> {
+                let mut out = GenericArray::default();
+                self.op
+                    .compute_final(&[], &mut out)
+                    .expect("MAC output buffer matches the algorithm");
+                self.op.init(&[]);
+                crypto_mac::Output::new(out)
+            }
+
+            fn verify(self, tag: &[u8]) -> std::result::Result<(), crypto_mac::MacError> {
+                self.op
+                    .compare_final(&[], tag)
+                    .map_err(|_| crypto_mac::MacError)
+            }
+        }
+    };
+}
+
+tee_mac!(HmacSha1, HmacSha1, U64, U20, "HMAC-SHA1 backed by the TEE.");
+tee_mac!(HmacSha224, HmacSha224, U64, U28, "HMAC-SHA224 backed by the TEE.");
+tee_mac!(HmacSha256, HmacSha256, U64, U32, "HMAC-SHA256 backed by the TEE.");
+tee_mac!(HmacSha384, HmacSha384, U128, U48, "HMAC-SHA384 backed by the TEE.");
+tee_mac!(HmacSha512, HmacSha512, U128, U64, "HMAC-SHA512 backed by the TEE.");
+tee_mac!(HmacSha3_224, HmacSha3_224, U144, U28, "HMAC-SHA3-224 backed by the TEE.");
+tee_mac!(HmacSha3_256, HmacSha3_256, U136, U32, "HMAC-SHA3-256 backed by the TEE.");
+tee_mac!(HmacSha3_384, HmacSha3_384, U104, U48, "HMAC-SHA3-384 backed by the TEE.");
+tee_mac!(HmacSha3_512, HmacSha3_512, U72, U64, "HMAC-SHA3-512 backed by the TEE.");
+
+macro_rules! tee_cmac {
+    ($name:ident, $key_size:ty, $doc:expr) => {
+        #[doc = $doc]
+        ///
+        /// Implements `crypto_mac::Mac` and `crypto_mac::NewMac`.
+        ///
+        /// # Panics
+        ///
+        /// 1) If `new` is called with a key rejected by the TEE.
+        /// 2) If the operation cannot be allocated when the instance is cloned.
+        pub struct $name(Mac);
+
+        impl crypto_mac::NewMac for $name {
+            type KeySize = $key_size;
+
+            fn new(key: &crypto_mac::Key<Self>) -> Self {
+                Self::new_from_slice(key).expect(concat!("invalid ", stringify!($name), " key"))
+            }
+
+            fn new_from_slice(key: &[u8]) -> std::result::Result<Self, crypto_mac::InvalidKeyLength> {
+                use cipher::generic_array::typenum::Unsigned;
+                if key.len() != <$key_size>::to_usize() {
+                    return Err(crypto_mac::InvalidKeyLength);
+                }
+                let op = secret_key(TransientObjectType::Aes, key)
+                    .and_then(|object| {
+                        let op = Mac::allocate(AlgorithmId::AesCmac, key.len() * 8)?;
+                        op.set_key(&object)?;
+                        Ok(op)
+                    })
+                    .map_err(|_| crypto_mac::InvalidKeyLength)?;
+                op.init(&[]);
+                Ok(Self(op))
+            }
+        }
+
+        impl Clone for $name {
+            fn clone(&self) -> Self {
+                use cipher::generic_array::typenum::Unsigned;
+                let mut op = Mac::allocate(AlgorithmId::AesCmac, <$key_size>::to_usize() * 8)
+                    .expect(concat!("failed to allocate ", stringify!($name), " operation"));
+                op.copy(&self.0);
+                Self(op)
+            }
+        }
+
+        impl crypto_mac::Mac for $name {
+            type OutputSize = U16;
+
+            fn update(&mut self, data: &[u8]) {
+                self.0.update(data);
+            }
+
+            fn reset(&mut self) {
+                self.0.reset();
+                self.0.init(&[]);
+            }
+
+            fn finalize(mut self) -> crypto_mac::Output<Self> {
+                self.finalize_reset()
+            }
+
+            fn finalize_reset(&mut self) -> crypto_mac::Output<Self> {
+                let mut out = GenericArray::default();
+                self.0
+                    .compute_final(&[], &mut out)
+                    .expect("MAC output buffer matches the algorithm");
+                self.0.init(&[]);
+                crypto_mac::Output::new(out)
+            }
+
+            fn verify(self, tag: &[u8]) -> std::result::Result<(), crypto_mac::MacError> {
+                self.0
+                    .compare_final(&[], tag)
+                    .map_err(|_| crypto_mac::MacError)
+            }
+        }
+    };
+}
+
+tee_cmac!(Aes128Cmac, U16, "AES-128-CMAC backed by the TEE.");
+tee_cmac!(Aes192Cmac, U24, "AES-192-CMAC backed by the TEE.");
+tee_cmac!(Aes256Cmac, U32, "AES-256-CMAC backed by the TEE.");
+
+macro_rules! tee_block_cipher {
+    ($name:ident, $object_type:ident, $algo:ident, $key_size:ty, $block_size:ty, $doc:expr) => {
+        #[doc = $doc]
+        ///
+        /// Implements `cipher::BlockEncrypt`, `cipher::BlockDecrypt` and
+        /// `cipher::NewBlockCipher` on top of the ECB mode of the TEE, so it can be plugged
+        /// into the RustCrypto block modes.
+        ///
+        /// # Panics
+        ///
+        /// 1) If `new` is called with a key rejected by the TEE.
+        pub struct $name {
+            encrypt: crate::Cipher,
+            decrypt: crate::Cipher,
+        }
+
+        impl $name {
+            fn with_key(key: &[u8]) -> Result<Self> {
+                let object = secret_key(TransientObjectType::$object_type, key)?;
+                let encrypt =
+                    crate::Cipher::allocate(AlgorithmId::$algo, OperationMode::Encrypt, key.len() * 8)?;
+                encrypt.set_key(&object)?;
+                let decrypt =
+                    crate::Cipher::allocate(AlgorithmId::$algo, OperationMode::Decrypt, key.len() * 8)?;
+                decrypt.set_key(&object)?;
+                Ok(Self { encrypt, decrypt })
+            }
+        }
+
+        impl cipher::NewBlockCipher for $name {
+            type KeySize = $key_size;
+
+            fn new(key: &cipher::BlockCipherKey<Self>) -> Self {
+                Self::with_key(key).expect(concat!("invalid ", stringify!($name), " key"))
+            }
+        }
+
+        impl cipher::BlockCipher for $name {
+            type BlockSize = $block_size;
+            type ParBlocks = U1;
+        }
+
+        impl cipher::BlockEncrypt for $name {
+            fn encrypt_block(&self, block: &mut cipher::Block<Self>) {
+                process_block(&self.encrypt, block);
+            }
+        }
+
+        impl cipher::BlockDecrypt for $name {
+            fn decrypt_block(&self, block: &mut cipher::Block<Self>) {
+                process_block(&self.decrypt, block);
+            }
+        }
+    };
+}
+
+// ECB operations are finalized after every block so the operation never buffers data.
+fn process_block(operation: &crate::Cipher, block: &mut [u8]) {
+    let mut out = vec![0u8; block.len()];
+    operation.init(&[]);
+    operation
+        .do_final(block, &mut out)
+        .expect("cipher output buffer matches the block size");
+    block.copy_from_slice(&out);
+}
+
+tee_block_cipher!(Aes128, Aes, AesEcbNopad, U16, U16, "AES-128 block cipher backed by the TEE.");
+tee_block_cipher!(Aes192, Aes, AesEcbNopad, U24, U16, "AES-192 block cipher backed by the TEE.");
+tee_block_cipher!(Aes256, Aes, AesEcbNopad, U32, U16, "AES-256 block cipher backed by the TEE.");
+tee_block_cipher!(Des, Des, DesEcbNopad, U8, U8, "DES block cipher backed by the TEE.");
+tee_block_cipher!(TdesEde3, Des3, Des3EcbNopad, U24, U8, "Triple DES (three keys) block cipher backed by the TEE.");
+
+/// A signature produced or checked by an [Asymmetric](../crypto_op/struct.Asymmetric.html)
+/// operation, in the encoding used by the TEE for its algorithm.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Signature(Vec<u8>);
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Signature({})", hex::encode(&self.0))
+    }
+}
+
+impl signature::Signature for Signature {
+    fn from_bytes(bytes: &[u8]) -> std::result::Result<Self, signature::Error> {
+        Ok(Self(bytes.to_vec()))
+    }
+}
+
+// Signature algorithm identifiers carry the hash in bits 12 to 15, as in the
+// `TEE_ALG_GET_DIGEST_HASH` macro of OP-TEE.
+fn message_digest_algorithm(algorithm: u32) -> Result<AlgorithmId> {
+    if algorithm >> 28 != 0x7 {
+        return Err(Error::new(ErrorKind::NotSupported));
+    }
+    // SM2 signs the hash of `Z_A || M`, which depends on the identity of the signer.
+    if algorithm == AlgorithmId::Sm2DsaSm3 as u32 {
+        return Err(Error::new(ErrorKind::NotSupported));
+    }
+    // ECDSA encodes the curve rather than the hash.
+    if algorithm & 0xFF == 0x41 {
+        return AlgorithmId::from_raw(algorithm)
+            .and_then(|algorithm| algorithm.ecdsa_digest())
+            .ok_or_else(|| Error::new(ErrorKind::NotSupported));
+    }
+    match (algorithm >> 12) & 0xF {
+        0x1 => Ok(AlgorithmId::Md5),
+        0x2 => Ok(AlgorithmId::Sha1),
+        0x3 => Ok(AlgorithmId::Sha224),
+        0x4 => Ok(AlgorithmId::Sha256),
+        0x5 => Ok(AlgorithmId::Sha384),
+        0x6 => Ok(AlgorithmId::Sha512),
+        _ => Err(Error::new(ErrorKind::NotSupported)),
+    }
+}
+
+fn message_digest(algorithm: u32, msg: &[u8]) -> Result<Vec<u8>> {
+    // Ed25519 hashes internally and signs the message itself.
+    if algorithm == AlgorithmId::Ed25519 as u32 {
+        return Ok(msg.to_vec());
+    }
+    let digest = Digest::allocate(message_digest_algorithm(algorithm)?)?;
+    let mut hash = [0u8; 64];
+    let hash_size = digest.do_final(msg, &mut hash)?;
+    Ok(hash[..hash_size].to_vec())
+}
+
+/// Hash the message with the digest of the signature algorithm and sign it with the key
+/// programmed in the operation. The operation SHALL be allocated in
+/// [Sign](../crypto_op/enum.OperationMode.html#variant.Sign) mode.
+impl signature::Signer<Signature> for Asymmetric {
+    fn try_sign(&self, msg: &[u8]) -> std::result::Result<Signature, signature::Error> {
+        let info = self.info();
+        let hash = message_digest(info.algorithm(), msg).map_err(signature::Error::from_source)?;
+        // Twice the key size holds RSA, DSA and ECDSA signatures alike.
+        let mut signature = vec![0u8; (info.key_size() as usize + 7) / 8 * 2];
+        let size = self
+            .sign_digest(&[], &hash, &mut signature)
+            .map_err(signature::Error::from_source)?;
+        signature.truncate(size);
+        Ok(Signature(signature))
+    }
+}
+
+/// Hash the message with the digest of the signature algorithm and verify it with the key
+/// programmed in the operation. The operation SHALL be allocated in
+/// [Verify](../crypto_op/enum.OperationMode.html#variant.Verify) mode.
+impl signature::Verifier<Signature> for Asymmetric {
+    fn verify(&self, msg: &[u8], signature: &Signature) -> std::result::Result<(), signature::Error> {
+        let hash = message_digest(self.info().algorithm(), msg).map_err(|_| signature::Error::new())?;
+        self.verify_digest(&[], &hash, &signature.0)
+            .map_err(|_| signature::Error::new())
+    }
+}
@@ -0,0 +1,148 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use optee_utee_sys as raw;
+use std::fmt;
+use std::ptr;
+
+/// A buffer whose whole memory can be overwritten with zeros.
+pub trait Wipe {
+    /// Overwrite the buffer with zeros.
+    fn wipe(&mut self);
+}
+
+fn fill_zero(buffer: *mut u8, len: usize) {
+    if len != 0 {
+        unsafe { raw::TEE_MemFill(buffer as _, 0, len as u32) };
+    }
+}
+
+impl Wipe for Vec<u8> {
+    // The spare capacity is wiped too, it may hold bytes left by a truncation.
+    fn wipe(&mut self) {
+        fill_zero(self.as_mut_ptr(), self.capacity());
+    }
+}
+
+impl Wipe for Box<[u8]> {
+    fn wipe(&mut self) {
+        fill_zero(self.as_mut_ptr(), self.len());
+    }
+}
+
+impl<const N: usize> Wipe for [u8; N] {
+    fn wipe(&mut self) {
+        fill_zero(self.as_mut_ptr(), N);
+    }
+}
+
+/// A buffer holding secret material, such as a key or a plaintext.
+///
+/// The buffer is wiped with `TEE_MemFill` when dropped, its `Debug` output does not show its
+/// content and two secrets compare in constant time. The content is only reachable through
+/// [expose](Secret::expose) and [expose_mut](Secret::expose_mut), which keeps the places where
+/// secret material is used easy to find.
+///
+/// # Example
+///
+/// ```no_run
+/// let key = object.ref_attribute_secret(AttributeId::SecretValue)?;
+/// let attr = AttributeMemref::from_secret(AttributeId::SecretValue, &key);
+/// ```
+pub struct Secret<T: Wipe>(T);
+
+impl<T: Wipe> Secret<T> {
+    /// Take ownership of `value`, which will be wiped on drop.
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// Return a reference to the secret content.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Wipe + AsMut<[u8]>> Secret<T> {
+    /// Return a mutable reference to the secret content. The buffer cannot be resized through
+    /// it, a reallocation would leave a copy of the content behind.
+    pub fn expose_mut(&mut self) -> &mut [u8] {
+        self.0.as_mut()
+    }
+}
+
+impl Secret<Vec<u8>> {
+    /// Allocate a zeroed secret buffer of `len` bytes.
+    pub fn zeroed(len: usize) -> Self {
+        Secret(vec![0u8; len])
+    }
+
+    /// Shorten the buffer to `len` bytes. The removed bytes stay in the spare capacity until
+    /// the secret is dropped.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len)
+    }
+}
+
+impl<T: Wipe + AsRef<[u8]>> Secret<T> {
+    /// Compare the content with `other` in a time that only depends on their lengths.
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        ct_eq(self.0.as_ref(), other)
+    }
+}
+
+// The lengths are not considered secret.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    // Keep the compiler from turning the loop into an early exit.
+    unsafe { ptr::read_volatile(&diff) == 0 }
+}
+
+impl<T: Wipe> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl<T: Wipe> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T: Wipe + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Secret(self.0.clone())
+    }
+}
+
+impl<T: Wipe + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+impl<T: Wipe + AsRef<[u8]>> Eq for Secret<T> {}
+
+impl<T: Wipe> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
@@ -15,11 +15,14 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::{Error, Result};
+use crate::{Error, ErrorKind, Result};
 use optee_utee_sys as raw;
 use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::Duration;
 
 /// A millisecond resolution structure for saving the time.
+#[repr(C)]
 pub struct Time {
     /// The field for the seconds.
     pub seconds: u32,
@@ -145,6 +148,21 @@ impl Time {
             raw::TEE_GetREETime(self as *mut _ as _);
         }
     }
+
+    /// Return the time as a [Duration](std::time::Duration) since the origin of its source.
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_secs(self.seconds as u64) + Duration::from_millis(self.millis as u64)
+    }
+}
+
+impl From<Duration> for Time {
+    /// The seconds are truncated to 32 bits and the sub-millisecond part is dropped.
+    fn from(duration: Duration) -> Self {
+        Time {
+            seconds: duration.as_secs() as u32,
+            millis: duration.subsec_millis(),
+        }
+    }
 }
 
 impl fmt::Display for Time {
@@ -156,3 +174,674 @@ impl fmt::Display for Time {
         )
     }
 }
+
+/// A measurement of the monotonic system time, similar to `std::time::Instant`.
+///
+/// It is read with `TEE_GetSystemTime`, which is never reset or rolled back during the life of
+/// the TA instance. Instants are only meaningful within one TA instance and are opaque: they can
+/// be compared and subtracted, but their origin is arbitrary.
+///
+/// # Example
+///
+/// ```no_run
+/// let start = Instant::now();
+/// // ...
+/// if start.elapsed() > Duration::from_millis(500) {
+///     return Err(Error::new(ErrorKind::Busy));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    /// Return the current system time.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the Implementation detects any error.
+    pub fn now() -> Self {
+        let mut time = Time::new();
+        time.system_time();
+        Instant(time.as_duration())
+    }
+
+    /// Return the time elapsed from `earlier` to `self`, or zero if `earlier` is later.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier).unwrap_or_default()
+    }
+
+    /// Return the time elapsed from `earlier` to `self`, or `None` if `earlier` is later.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0)
+    }
+
+    /// Return the time elapsed since `self` was measured.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    /// Return `self + duration`, or `None` on overflow.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add(duration).map(Instant)
+    }
+
+    /// Return `self - duration`, or `None` if it would be before the origin of the system time.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_sub(duration).map(Instant)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// 1) If the result overflows.
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// 1) If the result is before the origin of the system time.
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    /// Same as [duration_since](Instant::duration_since).
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+/// The state of the TA persistent time, as reported by `TEE_GetTAPersistentTime`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaTimeStatus {
+    /// The time is set, the value is the time elapsed since the origin chosen by the TA.
+    Set(Duration),
+    /// The time has never been set by this TA.
+    NotSet,
+    /// The time was set but cannot be trusted anymore, e.g. it was lost or rolled back together
+    /// with the storage backing it. It must be resynchronized before use.
+    NeedsReset,
+    /// The seconds do not fit in 32 bits. The value is the time with the seconds truncated to
+    /// 32 bits.
+    Overflow(Duration),
+}
+
+impl TaTimeStatus {
+    /// Return the time if it is [Set](TaTimeStatus::Set).
+    pub fn time(&self) -> Option<Duration> {
+        match self {
+            TaTimeStatus::Set(time) => Some(*time),
+            _ => None,
+        }
+    }
+}
+
+/// The TA persistent time, a real-time source whose origin is set by each TA and which persists
+/// across reboots.
+///
+/// Unlike [ta_time](Time::ta_time), which reports every state but a valid time as an error,
+/// [status](TaPersistentTime::status) tells apart a time which was never set from a time which
+/// may have been rolled back, so that the TA can decide how to resynchronize.
+///
+/// # Example
+///
+/// ```no_run
+/// let now = match TaPersistentTime::status()? {
+///     TaTimeStatus::Set(now) => now,
+///     TaTimeStatus::NotSet | TaTimeStatus::NeedsReset => {
+///         // `trusted_now` comes from a source the TA trusts, e.g. a signed timestamp.
+///         TaPersistentTime::resync(trusted_now)?;
+///         trusted_now
+///     }
+///     TaTimeStatus::Overflow(_) => return Err(Error::new(ErrorKind::Overflow)),
+/// };
+/// ```
+pub struct TaPersistentTime;
+
+impl TaPersistentTime {
+    /// Return the state of the TA persistent time.
+    ///
+    /// # Errors
+    ///
+    /// 1) Any error returned by `TEE_GetTAPersistentTime` other than `TimeNotSet`,
+    ///    `TimeNeedsReset` and `Overflow`, which are reported as a [TaTimeStatus](TaTimeStatus).
+    ///
+    /// # Panics
+    ///
+    /// 1) If the Implementation detects any error.
+    pub fn status() -> Result<TaTimeStatus> {
+        let mut time = Time::new();
+        match unsafe { raw::TEE_GetTAPersistentTime(&mut time as *mut _ as _) } {
+            raw::TEE_SUCCESS => Ok(TaTimeStatus::Set(time.as_duration())),
+            raw::TEE_ERROR_TIME_NOT_SET => Ok(TaTimeStatus::NotSet),
+            raw::TEE_ERROR_TIME_NEEDS_RESET => Ok(TaTimeStatus::NeedsReset),
+            raw::TEE_ERROR_OVERFLOW => Ok(TaTimeStatus::Overflow(time.as_duration())),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
+    /// Set the TA persistent time to `now`, which clears the
+    /// [NotSet](TaTimeStatus::NotSet) and [NeedsReset](TaTimeStatus::NeedsReset) states.
+    ///
+    /// The new time is only as trustworthy as `now`: resynchronizing from the REE time, for
+    /// example, gives up the rollback protection.
+    ///
+    /// # Errors
+    ///
+    /// 1) `Overflow`: If the seconds of `now` do not fit in 32 bits.
+    /// 2) `OutOfMemory`: If not enough memory is available to complete the operation.
+    /// 3) `StorageNoSpace`: If insufficient storage space is available to complete the operation.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the Implementation detects any error.
+    pub fn resync(now: Duration) -> Result<()> {
+        if now.as_secs() > u32::MAX as u64 {
+            return Err(Error::new(ErrorKind::Overflow));
+        }
+        Time::from(now).set_ta_time()
+    }
+}
+
+/// The source a time was read from, which tells how far it can be trusted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeSource {
+    /// The REE time, as trusted as the REE itself: the normal world may set it to any value.
+    Ree,
+    /// The TA persistent time, set by the TA and protected by the TEE against rollback.
+    TaPersistent,
+}
+
+impl fmt::Display for TimeSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeSource::Ree => f.write_str("REE"),
+            TimeSource::TaPersistent => f.write_str("TA persistent"),
+        }
+    }
+}
+
+/// A UTC date and time with millisecond resolution, in the proleptic Gregorian calendar.
+///
+/// Leap seconds are not represented. Date-times are ordered chronologically.
+///
+/// # Example
+///
+/// ```no_run
+/// let not_after = UtcDateTime::parse_generalized_time("20300101000000Z")?;
+/// let now = Timestamp::ree();
+/// if now.utc() > not_after {
+///     trace_println!("expired at {}, {} time is {}", not_after, now.source(), now.utc());
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UtcDateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    millis: u16,
+}
+
+impl UtcDateTime {
+    /// Create a date-time from its fields.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If a field is out of range, e.g. February 30, or the year is after
+    ///    9999.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        millis: u16,
+    ) -> Result<Self> {
+        if year > 9999
+            || !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+            || millis > 999
+        {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        Ok(UtcDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            millis,
+        })
+    }
+
+    /// Convert a time elapsed since the Unix epoch, 1970-01-01T00:00:00Z.
+    ///
+    /// # Errors
+    ///
+    /// 1) `Overflow`: If the date-time is after the year 9999.
+    pub fn from_unix(since_epoch: Duration) -> Result<Self> {
+        let secs = since_epoch.as_secs();
+        if secs > MAX_UNIX_SECONDS as u64 {
+            return Err(Error::new(ErrorKind::Overflow));
+        }
+        let (year, month, day) = civil_from_days((secs / 86400) as i64);
+        let secs_of_day = secs % 86400;
+        Ok(UtcDateTime {
+            year: year as u16,
+            month,
+            day,
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day / 60 % 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            millis: since_epoch.subsec_millis() as u16,
+        })
+    }
+
+    /// Convert a [Time](Time) holding the time elapsed since the Unix epoch, as the REE time
+    /// does and as a TA persistent time kept in Unix time does.
+    pub fn from_time(time: &Time) -> Self {
+        // 32 bits of seconds never reach the year 9999.
+        Self::from_unix(time.as_duration()).unwrap()
+    }
+
+    /// Return the number of seconds since the Unix epoch, negative before 1970.
+    pub fn unix_seconds(&self) -> i64 {
+        days_from_civil(self.year as i64, self.month, self.day) * 86400
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+    }
+
+    /// Return the time elapsed since the Unix epoch, `None` before 1970.
+    pub fn unix_time(&self) -> Option<Duration> {
+        let secs = self.unix_seconds();
+        if secs < 0 {
+            return None;
+        }
+        Some(Duration::from_secs(secs as u64) + Duration::from_millis(self.millis as u64))
+    }
+
+    /// Return the time elapsed from `earlier` to `self`, `None` if `earlier` is later.
+    pub fn checked_duration_since(&self, earlier: &UtcDateTime) -> Option<Duration> {
+        if self < earlier {
+            return None;
+        }
+        let self_ms = self.unix_seconds() * 1000 + self.millis as i64;
+        let earlier_ms = earlier.unix_seconds() * 1000 + earlier.millis as i64;
+        Some(Duration::from_millis((self_ms - earlier_ms) as u64))
+    }
+
+    /// Return the year, from 0 to 9999.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Return the month, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// Return the day of the month, from 1 to 31.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Return the hour, from 0 to 23.
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// Return the minute, from 0 to 59.
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// Return the second, from 0 to 59.
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// Return the milliseconds within the second, from 0 to 999.
+    pub fn millis(&self) -> u16 {
+        self.millis
+    }
+
+    /// Format as RFC 3339, e.g. `2024-02-29T13:05:09Z`, or `2024-02-29T13:05:09.250Z` when the
+    /// milliseconds are not zero.
+    pub fn to_rfc3339(&self) -> String {
+        let mut res = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        );
+        if self.millis != 0 {
+            res.push_str(&format!(".{:03}", self.millis));
+        }
+        res.push('Z');
+        res
+    }
+
+    /// Parse an RFC 3339 date-time. A numeric offset is applied to get the UTC time, and
+    /// fractional seconds beyond milliseconds are truncated.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `src` is not an RFC 3339 date-time, or is a leap second.
+    pub fn parse_rfc3339(src: &str) -> Result<Self> {
+        let s = src.as_bytes();
+        if s.len() < 20 || s[4] != b'-' || s[7] != b'-' || s[13] != b':' || s[16] != b':' {
+            return Err(bad_format());
+        }
+        if !matches!(s[10], b'T' | b't' | b' ') {
+            return Err(bad_format());
+        }
+        let mut res = Self::from_digits(
+            digits(&s[0..4])?,
+            &[&s[5..7], &s[8..10], &s[11..13], &s[14..16], &s[17..19]],
+        )?;
+        let mut rest = &s[19..];
+        if rest[0] == b'.' {
+            let len = rest[1..].iter().take_while(|c| c.is_ascii_digit()).count();
+            if len == 0 {
+                return Err(bad_format());
+            }
+            res.millis = fraction_millis(&rest[1..1 + len])?;
+            rest = &rest[1 + len..];
+        }
+        let offset = match rest {
+            b"Z" | b"z" => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+                let hours = digits(&[*h1, *h2])?;
+                let minutes = digits(&[*m1, *m2])?;
+                if hours > 23 || minutes > 59 {
+                    return Err(bad_format());
+                }
+                let offset = (hours * 3600 + minutes * 60) as i64;
+                if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            _ => return Err(bad_format()),
+        };
+        if offset != 0 {
+            res = Self::from_unix_seconds(res.unix_seconds() - offset, res.millis)?;
+        }
+        Ok(res)
+    }
+
+    /// Format as an ASN.1 UTCTime in the form required by DER, `YYMMDDHHMMSSZ`. The
+    /// milliseconds are dropped.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If the year is not within 1950 to 2049, which UTCTime cannot
+    ///    represent.
+    pub fn to_utc_time(&self) -> Result<String> {
+        if self.year < 1950 || self.year > 2049 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        Ok(format!(
+            "{:02}{:02}{:02}{:02}{:02}{:02}Z",
+            self.year % 100,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second
+        ))
+    }
+
+    /// Parse an ASN.1 UTCTime in the form required by DER, `YYMMDDHHMMSSZ`. Years below 50 are
+    /// in the 21st century, as in RFC 5280.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `src` is not in this form or is not a valid date-time.
+    pub fn parse_utc_time(src: &str) -> Result<Self> {
+        let s = src.as_bytes();
+        if s.len() != 13 || s[12] != b'Z' {
+            return Err(bad_format());
+        }
+        let year = digits(&s[0..2])?;
+        Self::from_digits(
+            if year < 50 { 2000 + year } else { 1900 + year },
+            &[&s[2..4], &s[4..6], &s[6..8], &s[8..10], &s[10..12]],
+        )
+    }
+
+    /// Format as an ASN.1 GeneralizedTime in the form required by DER, `YYYYMMDDHHMMSSZ`,
+    /// with the non-zero milliseconds as a fraction without trailing zeros.
+    pub fn to_generalized_time(&self) -> String {
+        let mut res = format!(
+            "{:04}{:02}{:02}{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        );
+        if self.millis != 0 {
+            res.push_str(format!(".{:03}", self.millis).trim_end_matches('0'));
+        }
+        res.push('Z');
+        res
+    }
+
+    /// Parse an ASN.1 GeneralizedTime in the form required by DER, `YYYYMMDDHHMMSSZ` with an
+    /// optional fraction of second. Digits beyond milliseconds are truncated.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `src` is not in this form or is not a valid date-time.
+    pub fn parse_generalized_time(src: &str) -> Result<Self> {
+        let s = src.as_bytes();
+        if s.len() < 15 || s[s.len() - 1] != b'Z' {
+            return Err(bad_format());
+        }
+        let mut res = Self::from_digits(
+            digits(&s[0..4])?,
+            &[&s[4..6], &s[6..8], &s[8..10], &s[10..12], &s[12..14]],
+        )?;
+        match &s[14..s.len() - 1] {
+            [] => {}
+            [b'.', fraction @ ..] if !fraction.is_empty() && !fraction.ends_with(b"0") => {
+                res.millis = fraction_millis(fraction)?;
+            }
+            _ => return Err(bad_format()),
+        }
+        Ok(res)
+    }
+
+    // Build from the year and the two-digit month, day, hour, minute and second.
+    fn from_digits(year: u64, fields: &[&[u8]; 5]) -> Result<Self> {
+        let mut values = [0u8; 5];
+        for (value, field) in values.iter_mut().zip(fields.iter()) {
+            *value = digits(field)? as u8;
+        }
+        let [month, day, hour, minute, second] = values;
+        Self::new(year as u16, month, day, hour, minute, second, 0).map_err(|_| bad_format())
+    }
+
+    fn from_unix_seconds(secs: i64, millis: u16) -> Result<Self> {
+        if secs < days_from_civil(0, 1, 1) * 86400 || secs > MAX_UNIX_SECONDS {
+            return Err(bad_format());
+        }
+        let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+        let secs_of_day = secs.rem_euclid(86400);
+        Ok(UtcDateTime {
+            year: year as u16,
+            month,
+            day,
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day / 60 % 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            millis,
+        })
+    }
+}
+
+impl fmt::Display for UtcDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_rfc3339())
+    }
+}
+
+/// A UTC date-time labelled with the [TimeSource](TimeSource) it was read from.
+///
+/// The TA persistent time is assumed to be kept in Unix time, i.e. set with
+/// [TaPersistentTime::resync](TaPersistentTime::resync) to the time elapsed since the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timestamp {
+    utc: UtcDateTime,
+    source: TimeSource,
+}
+
+impl Timestamp {
+    /// Read the current REE time.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the Implementation detects any error.
+    pub fn ree() -> Self {
+        let mut time = Time::new();
+        time.ree_time();
+        Timestamp {
+            utc: UtcDateTime::from_time(&time),
+            source: TimeSource::Ree,
+        }
+    }
+
+    /// Read the current TA persistent time.
+    ///
+    /// # Errors
+    ///
+    /// 1) `TimeNotSet`, `TimeNeedsReset` or `Overflow`: If the
+    ///    [status](TaPersistentTime::status) of the time is not
+    ///    [Set](TaTimeStatus::Set).
+    ///
+    /// # Panics
+    ///
+    /// 1) If the Implementation detects any error.
+    pub fn ta_persistent() -> Result<Self> {
+        let since_epoch = match TaPersistentTime::status()? {
+            TaTimeStatus::Set(time) => time,
+            TaTimeStatus::NotSet => return Err(Error::new(ErrorKind::TimeNotSet)),
+            TaTimeStatus::NeedsReset => return Err(Error::new(ErrorKind::TimeNeedsReset)),
+            TaTimeStatus::Overflow(_) => return Err(Error::new(ErrorKind::Overflow)),
+        };
+        Ok(Timestamp {
+            utc: UtcDateTime::from_unix(since_epoch)?,
+            source: TimeSource::TaPersistent,
+        })
+    }
+
+    /// Return the date-time.
+    pub fn utc(&self) -> UtcDateTime {
+        self.utc
+    }
+
+    /// Return the source the time was read from.
+    pub fn source(&self) -> TimeSource {
+        self.source
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} time)", self.utc, self.source)
+    }
+}
+
+// 9999-12-31T23:59:59Z
+const MAX_UNIX_SECONDS: i64 = 253402300799;
+
+fn bad_format() -> Error {
+    Error::new(ErrorKind::BadFormat)
+}
+
+fn digits(s: &[u8]) -> Result<u64> {
+    s.iter().try_fold(0u64, |acc, c| match c {
+        b'0'..=b'9' => Ok(acc * 10 + (c - b'0') as u64),
+        _ => Err(bad_format()),
+    })
+}
+
+// Milliseconds of a fraction of second, truncating the extra digits.
+fn fraction_millis(fraction: &[u8]) -> Result<u16> {
+    let mut ms = [b'0'; 3];
+    if !fraction.iter().all(u8::is_ascii_digit) {
+        return Err(bad_format());
+    }
+    for (m, c) in ms.iter_mut().zip(fraction) {
+        *m = *c;
+    }
+    Ok(digits(&ms)? as u16)
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let y = year - if month <= 2 { 1 } else { 0 };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// Proleptic Gregorian date of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -15,12 +15,34 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::trace_buffer;
 use libc;
 use optee_utee_sys as raw;
+use std::ffi::CString;
 use std::fmt;
 use std::io;
 use std::io::Write;
 
+pub use optee_utee_sys::{TRACE_DEBUG, TRACE_ERROR, TRACE_FLOW, TRACE_INFO};
+
+/// The most verbose trace level compiled into the leveled macros such as
+/// [trace_debug](../macro.trace_debug.html), `TRACE_FLOW` by default.
+///
+/// It is lowered with one of the `max_level_off`, `max_level_error`, `max_level_info` and
+/// `max_level_debug` features, the least verbose one wins. A macro above this level expands
+/// to dead code, so neither its arguments nor its message end up in the TA.
+pub const MAX_LEVEL: i32 = if cfg!(feature = "max_level_off") {
+    0
+} else if cfg!(feature = "max_level_error") {
+    TRACE_ERROR
+} else if cfg!(feature = "max_level_info") {
+    TRACE_INFO
+} else if cfg!(feature = "max_level_debug") {
+    TRACE_DEBUG
+} else {
+    TRACE_FLOW
+};
+
 pub struct Trace;
 
 impl Trace {
@@ -37,10 +59,39 @@ impl Trace {
         }
     }
 
+    /// Print a message at `level` through `trace_printf`, prefixed like the messages of the C
+    /// API with the level, the `trace_ext_prefix` of the TA, the thread, `function` and `line`.
+    /// The message is dropped if `level` is above the current trace level. It is also recorded
+    /// in the [trace buffer](../trace_buffer/index.html), prefixed with the level, `function`
+    /// and `line`.
+    pub fn _print_level(level: i32, function: &str, line: u32, fmt: fmt::Arguments) {
+        if level > Trace::get_level() {
+            return;
+        }
+        let message = fmt::format(fmt);
+        let record = format!("{}/{}:{} {}\n", level_char(level), function, line, message);
+        trace_buffer::record(record.as_bytes(), Some(level));
+        // A NUL would end the C strings early.
+        let function = CString::new(function.replace('\0', "")).unwrap_or_default();
+        let message = CString::new(message.replace('\0', "\\0")).unwrap_or_default();
+        unsafe {
+            raw::trace_printf(
+                function.as_ptr() as _,
+                line as _,
+                level,
+                true,
+                "%s\0".as_ptr() as _,
+                message.as_ptr() as *const libc::c_char,
+            );
+        }
+    }
+
     pub fn set_level(level: i32) {
         unsafe {
             raw::trace_set_level(level);
         }
+        #[cfg(feature = "log")]
+        log::set_max_level(level_filter(level.min(MAX_LEVEL)));
     }
 
     pub fn get_level() -> i32 {
@@ -53,6 +104,7 @@ impl io::Write for Trace {
         unsafe {
             raw::_utee_log(buf.as_ptr() as *const libc::c_void, buf.len());
         }
+        trace_buffer::record(buf, None);
         Ok(buf.len())
     }
 
@@ -60,3 +112,118 @@ impl io::Write for Trace {
         Ok(())
     }
 }
+
+/// A [log](https://docs.rs/log/0.4) backend writing the records to the trace output. This
+/// type is only available with the `log` feature.
+///
+/// Records go through `trace_printf` like the `EMSG`/`IMSG`/`DMSG`/`FMSG` macros of the C API,
+/// so they carry the level, the `trace_ext_prefix` of the TA and the thread, followed by the
+/// module path and line of the record. OP-TEE has no warning level, the `log` levels map onto
+/// the trace levels as follows:
+///
+/// 1) `Error` and `Warn`: `TRACE_ERROR`.
+/// 2) `Info`: `TRACE_INFO`.
+/// 3) `Debug`: `TRACE_DEBUG`.
+/// 4) `Trace`: `TRACE_FLOW`.
+///
+/// A record is printed when its trace level is at most the current one, which starts at the
+/// `trace_level` static of the TA (`TRACE_LEVEL` in `ta_static.rs`) and is changed with
+/// [set_level](Trace::set_level). Like in C, a record longer than the trace buffer of OP-TEE
+/// is truncated.
+///
+/// # Examples
+///
+/// ``` no_run
+/// #[ta_create]
+/// fn create() -> Result<()> {
+///     TraceLogger::init().unwrap();
+///     log::info!("TA created");
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "log")]
+pub struct TraceLogger;
+
+#[cfg(feature = "log")]
+static LOGGER: TraceLogger = TraceLogger;
+
+#[cfg(feature = "log")]
+impl TraceLogger {
+    /// Install the trace output as the logger of the `log` crate, usually from the
+    /// `ta_create` entry point.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a logger is already installed.
+    pub fn init() -> Result<(), log::SetLoggerError> {
+        log::set_logger(&LOGGER)?;
+        log::set_max_level(level_filter(Trace::get_level().min(MAX_LEVEL)));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "log")]
+impl log::Log for TraceLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let level = trace_level(metadata.level());
+        level <= MAX_LEVEL && level <= Trace::get_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let path = record.module_path().unwrap_or_else(|| record.target());
+        Trace::_print_level(
+            trace_level(record.level()),
+            path,
+            record.line().unwrap_or(0),
+            *record.args(),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(feature = "log")]
+fn trace_level(level: log::Level) -> i32 {
+    match level {
+        log::Level::Error | log::Level::Warn => TRACE_ERROR,
+        log::Level::Info => TRACE_INFO,
+        log::Level::Debug => TRACE_DEBUG,
+        log::Level::Trace => TRACE_FLOW,
+    }
+}
+
+#[cfg(feature = "log")]
+fn level_filter(trace_level: i32) -> log::LevelFilter {
+    match trace_level {
+        level if level < TRACE_ERROR => log::LevelFilter::Off,
+        TRACE_ERROR => log::LevelFilter::Warn,
+        TRACE_INFO => log::LevelFilter::Info,
+        TRACE_DEBUG => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    }
+}
+
+// The character in front of a record of the given level in the OP-TEE trace output.
+fn level_char(level: i32) -> char {
+    match level {
+        TRACE_ERROR => 'E',
+        TRACE_INFO => 'I',
+        TRACE_DEBUG => 'D',
+        _ => 'F',
+    }
+}
+
+// Reduce the type name of the item `f` defined by `__function_name!`, such as
+// `ta::invoke_command::f`, to the name of the enclosing function.
+#[doc(hidden)]
+pub fn _function_name<F>(_f: F) -> &'static str {
+    let path = std::any::type_name::<F>();
+    let mut path = path.strip_suffix("::f").unwrap_or(path);
+    while let Some(outer) = path.strip_suffix("::{{closure}}") {
+        path = outer;
+    }
+    path.rsplit("::").next().unwrap_or(path)
+}
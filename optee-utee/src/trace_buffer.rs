@@ -0,0 +1,327 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A bounded ring buffer in TA memory which keeps a copy of the trace output.
+//!
+//! Production OP-TEE builds discard the secure world console, the buffer keeps the output of
+//! [trace_println](../macro.trace_println.html), the leveled macros such as
+//! [trace_error](../macro.trace_error.html) and the `log` backend so that a client can fetch
+//! it later. Once the buffer is full, the oldest bytes are overwritten.
+//!
+//! The [ta_invoke_command](../attr.ta_invoke_command.html) entry point answers the reserved
+//! command [DRAIN_COMMAND](DRAIN_COMMAND) without calling the TA: it moves the oldest bytes of
+//! the buffer into an output memref. Only clients accepted by the function given to
+//! [set_authorizer](set_authorizer) may drain the buffer, all of them are refused by default.
+//! The host side is `Session::fetch_trace_buffer` of `optee-teec`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! #[ta_create]
+//! fn create() -> Result<()> {
+//!     trace_buffer::enable_persistent(16 * 1024, b"trace")?;
+//!     trace_buffer::set_authorizer(|client| client.login() == raw::TEE_LOGIN_USER);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::object::{DataFlag, ObjectStorageConstants, PersistentObject};
+use crate::time::Instant;
+use crate::trace::TRACE_ERROR;
+use crate::{Error, ErrorKind, ParamType, Parameters, Result, Uuid};
+use optee_utee_sys as raw;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Duration;
+
+/// The command reserved by the entry point generated by
+/// [ta_invoke_command](../attr.ta_invoke_command.html) to drain the buffer.
+///
+/// Its parameters are:
+///
+/// 1) `MemrefOutput`: Receives the oldest bytes of the buffer, which are removed from it. Its
+///    size is updated to the number of bytes written.
+/// 2) `ValueOutput`: `a` is the number of bytes still in the buffer and `b` the number of bytes
+///    overwritten since the previous drain.
+/// 3) `None`.
+/// 4) `None`.
+///
+/// The command fails with `AccessDenied` for a client refused by the authorizer and with
+/// `ItemNotFound` when the buffer is not enabled.
+pub const DRAIN_COMMAND: u32 = 0xFFFF_FF00;
+
+// Minimum time between two saves of a persistent buffer triggered by `TRACE_ERROR` records.
+const ERROR_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A function accepting or refusing a client for [DRAIN_COMMAND](DRAIN_COMMAND), see
+/// [set_authorizer](set_authorizer).
+pub type Authorizer = fn(&ClientIdentity) -> bool;
+
+/// The identity of the client of the current session, from the `gpd.client.identity`
+/// property.
+#[derive(Copy, Clone)]
+pub struct ClientIdentity {
+    login: u32,
+    uuid: Uuid,
+}
+
+impl ClientIdentity {
+    /// Return the identity of the client of the current session.
+    pub fn current() -> Result<Self> {
+        let mut identity = raw::TEE_Identity {
+            login: 0,
+            uuid: raw::TEE_UUID {
+                timeLow: 0,
+                timeMid: 0,
+                timeHiAndVersion: 0,
+                clockSeqAndNode: [0; 8],
+            },
+        };
+        match unsafe {
+            raw::TEE_GetPropertyAsIdentity(
+                raw::TEE_PROPSET_CURRENT_CLIENT,
+                "gpd.client.identity\0".as_ptr() as _,
+                &mut identity,
+            )
+        } {
+            raw::TEE_SUCCESS => Ok(Self {
+                login: identity.login,
+                uuid: Uuid::new_raw(
+                    identity.uuid.timeLow,
+                    identity.uuid.timeMid,
+                    identity.uuid.timeHiAndVersion,
+                    identity.uuid.clockSeqAndNode,
+                ),
+            }),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
+    /// Return the login method of the client, one of the `TEE_LOGIN_*` constants.
+    pub fn login(&self) -> u32 {
+        self.login
+    }
+
+    /// Return the UUID of the client, the TA UUID for `TEE_LOGIN_TRUSTED_APP` and a value
+    /// derived from the login method otherwise.
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+}
+
+struct RingBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    dropped: u32,
+    object_id: Option<Vec<u8>>,
+    last_flush: Option<Instant>,
+}
+
+impl RingBuffer {
+    fn push(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        self.data.drain(..overflow);
+        self.dropped = self
+            .dropped
+            .saturating_add(overflow.min(u32::MAX as usize) as u32);
+        self.data.extend(bytes);
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let object_id = match &self.object_id {
+            Some(object_id) => object_id,
+            None => return Ok(()),
+        };
+        self.last_flush = Some(Instant::now());
+        let (front, back) = self.data.as_slices();
+        PersistentObject::create(
+            ObjectStorageConstants::Private,
+            object_id,
+            DataFlag::ACCESS_WRITE | DataFlag::OVERWRITE,
+            None,
+            &[front, back].concat(),
+        )?;
+        Ok(())
+    }
+
+    // Whether a `TRACE_ERROR` record saves a persistent buffer, at most once per interval.
+    fn error_flush_due(&self) -> bool {
+        self.object_id.is_some()
+            && self
+                .last_flush
+                .map_or(true, |last| last.elapsed() >= ERROR_FLUSH_INTERVAL)
+    }
+}
+
+// A TA instance is only entered by one thread at a time.
+struct Global<T>(T);
+
+unsafe impl<T> Sync for Global<T> {}
+
+static BUFFER: Global<RefCell<Option<RingBuffer>>> = Global(RefCell::new(None));
+static AUTHORIZER: Global<Cell<Option<Authorizer>>> = Global(Cell::new(None));
+
+/// Start recording the trace output into a buffer of `capacity` bytes, replacing the current
+/// buffer.
+pub fn enable(capacity: usize) {
+    *BUFFER.0.borrow_mut() = Some(RingBuffer {
+        data: VecDeque::with_capacity(capacity),
+        capacity,
+        dropped: 0,
+        object_id: None,
+        last_flush: None,
+    });
+}
+
+/// Start recording the trace output into a buffer of `capacity` bytes saved in the private
+/// storage under `object_id`, replacing the current buffer.
+///
+/// The content saved by a previous instance of the TA is restored first. The buffer is saved
+/// by [flush](flush), by a drain which empties it and by a record at the `TRACE_ERROR` level,
+/// so that the errors leading to a crash of the TA are kept. Records at the `TRACE_ERROR` level
+/// save the buffer at most once per second: in a burst of errors, those following the first
+/// are saved by the next save.
+///
+/// # Errors
+///
+/// 1) `AccessConflict`, `StorageNotAvailable`, `OutOfMemory`: If the saved content cannot be
+///    read.
+pub fn enable_persistent(capacity: usize, object_id: &[u8]) -> Result<()> {
+    let mut buffer = RingBuffer {
+        data: VecDeque::with_capacity(capacity),
+        capacity,
+        dropped: 0,
+        object_id: Some(object_id.to_vec()),
+        last_flush: None,
+    };
+    match PersistentObject::open(
+        ObjectStorageConstants::Private,
+        object_id,
+        DataFlag::ACCESS_READ,
+    ) {
+        Ok(object) => {
+            let mut saved = vec![0u8; object.info()?.data_size()];
+            let len = object.read(&mut saved)? as usize;
+            buffer.push(&saved[..len]);
+            buffer.dropped = 0;
+        }
+        Err(e) if e.kind() == ErrorKind::ItemNotFound => (),
+        Err(e) => return Err(e),
+    }
+    *BUFFER.0.borrow_mut() = Some(buffer);
+    Ok(())
+}
+
+/// Stop recording and free the buffer. A persistent buffer stays in the storage.
+pub fn disable() {
+    *BUFFER.0.borrow_mut() = None;
+}
+
+/// Return whether the trace output is recorded.
+pub fn is_enabled() -> bool {
+    BUFFER.0.borrow().is_some()
+}
+
+/// Accept the clients for which `authorizer` returns `true` for [DRAIN_COMMAND](DRAIN_COMMAND).
+pub fn set_authorizer(authorizer: Authorizer) {
+    AUTHORIZER.0.set(Some(authorizer));
+}
+
+/// Remove and return the content of the buffer, empty if the buffer is not enabled.
+pub fn take() -> Vec<u8> {
+    match BUFFER.0.borrow_mut().as_mut() {
+        Some(buffer) => {
+            buffer.dropped = 0;
+            let data = mem::take(&mut buffer.data);
+            // Failing to save leaves the stale content in the storage, it is harmless.
+            let _ = buffer.flush();
+            data.into()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Save a persistent buffer to the storage, does nothing for a buffer in memory only.
+pub fn flush() -> Result<()> {
+    match BUFFER.0.borrow_mut().as_mut() {
+        Some(buffer) => buffer.flush(),
+        None => Ok(()),
+    }
+}
+
+// Append trace output to the buffer. `level` is the trace level of a complete record, and
+// `None` for the raw output of `trace_print!`.
+pub(crate) fn record(bytes: &[u8], level: Option<i32>) {
+    // Skipped if the buffer is in use, e.g. when recording from within the buffer.
+    if let Ok(mut buffer) = BUFFER.0.try_borrow_mut() {
+        if let Some(buffer) = buffer.as_mut() {
+            buffer.push(bytes);
+            if level == Some(TRACE_ERROR) && buffer.error_flush_due() {
+                let _ = buffer.flush();
+            }
+        }
+    }
+}
+
+// Move the oldest bytes of the buffer into `out`, and return the number of bytes moved, the
+// number of bytes left and the number of bytes overwritten since the previous drain.
+fn drain(out: &mut [u8]) -> Result<(usize, usize, u32)> {
+    let mut buffer = BUFFER.0.borrow_mut();
+    let buffer = buffer
+        .as_mut()
+        .ok_or_else(|| Error::new(ErrorKind::ItemNotFound))?;
+    let len = out.len().min(buffer.data.len());
+    for (dst, src) in out.iter_mut().zip(buffer.data.drain(..len)) {
+        *dst = src;
+    }
+    let dropped = mem::replace(&mut buffer.dropped, 0);
+    // A client drains the buffer in several commands, it is saved once empty.
+    if len > 0 && buffer.data.is_empty() {
+        let _ = buffer.flush();
+    }
+    Ok((len, buffer.data.len(), dropped))
+}
+
+// Handle DRAIN_COMMAND for the entry point generated by `ta_invoke_command`.
+#[doc(hidden)]
+pub fn _invoke_drain(params: &mut Parameters) -> Result<()> {
+    let authorized = match AUTHORIZER.0.get() {
+        Some(authorizer) => authorizer(&ClientIdentity::current()?),
+        None => false,
+    };
+    if !authorized {
+        return Err(Error::new(ErrorKind::AccessDenied));
+    }
+    match (
+        params.0.param_type,
+        params.1.param_type,
+        params.2.param_type,
+        params.3.param_type,
+    ) {
+        (ParamType::MemrefOutput, ParamType::ValueOutput, ParamType::None, ParamType::None) => (),
+        _ => return Err(Error::new(ErrorKind::BadParameters)),
+    }
+    let mut memref = unsafe { params.0.as_memref()? };
+    let (len, left, dropped) = drain(memref.buffer())?;
+    memref.set_updated_size(len);
+    let mut value = unsafe { params.1.as_value()? };
+    value.set_a(left as u32);
+    value.set_b(dropped);
+    Ok(())
+}
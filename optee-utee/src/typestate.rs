@@ -0,0 +1,523 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Typestate wrappers of the [Cipher](../crypto_op/struct.Cipher.html),
+//! [Mac](../crypto_op/struct.Mac.html) and [AE](../crypto_op/struct.AE.html) operations.
+//!
+//! The state of an operation is part of its type, so that calling a function in the wrong
+//! state, which the TEE answers with a panic, fails to compile instead:
+//!
+//! 1) [Unkeyed](Unkeyed): allocated, only a key can be set.
+//! 2) [Keyed](Keyed): a key is set and the operation is in the initial state, it can be
+//!    initialized or given another key.
+//! 3) [Active](Active): initialized, data can be processed until the operation is finalized,
+//!    which returns it to [Keyed](Keyed).
+//!
+//! Transitions consume the operation and return it in its new state. Setting a key checks
+//! that the key object is initialized, fits the maximum key size of the operation and allows
+//! the usage required by its mode, returning an error where the TEE would panic. A failed
+//! finalization returns the operation along with the error, in the state the TEE left it in:
+//! still [Active](Active) after a `ShortBuffer` error, so that the call can be retried with a
+//! bigger buffer.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use optee_utee::typestate::Cipher;
+//!
+//! let cipher = Cipher::allocate(AlgorithmId::AesCtr, OperationMode::Encrypt, 128)?;
+//! let mut cipher = cipher.set_key(&key)?.init(&iv);
+//! let len = cipher.update(&plain, &mut encrypted)?;
+//! let (cipher, last) = cipher.do_final(&[], &mut encrypted[len..])?;
+//! ```
+
+use crate::{
+    crypto_op, AlgorithmId, Error, ErrorKind, HandleFlag, ObjHandle, OperationInfo, OperationMode,
+    Result,
+};
+use std::marker::PhantomData;
+
+/// The result of a finalization, whose error comes with the operation.
+pub type FinalResult<T, O> = std::result::Result<T, (O, Error)>;
+
+/// The error of [AE::decrypt_final](AE::decrypt_final), with the operation.
+pub enum DecryptFinalError {
+    /// The output buffer is not large enough, the operation is still active.
+    ShortBuffer(AE<Active>),
+    /// The computed tag does not match, the operation is back in the [Keyed](Keyed) state.
+    MacInvalid(AE<Keyed>),
+}
+
+impl<S: State> From<(Cipher<S>, Error)> for Error {
+    fn from((_, error): (Cipher<S>, Error)) -> Error {
+        error
+    }
+}
+
+impl<S: State> From<(Mac<S>, Error)> for Error {
+    fn from((_, error): (Mac<S>, Error)) -> Error {
+        error
+    }
+}
+
+impl<S: State> From<(AE<S>, Error)> for Error {
+    fn from((_, error): (AE<S>, Error)) -> Error {
+        error
+    }
+}
+
+impl From<DecryptFinalError> for Error {
+    fn from(error: DecryptFinalError) -> Error {
+        match error {
+            DecryptFinalError::ShortBuffer(_) => Error::new(ErrorKind::ShortBuffer),
+            DecryptFinalError::MacInvalid(_) => Error::new(ErrorKind::MacInvalid),
+        }
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// The state of a typestate operation.
+pub trait State: private::Sealed {}
+
+/// The operation has no key.
+pub struct Unkeyed;
+/// A key is set and the operation is in the initial state.
+pub struct Keyed;
+/// The operation is initialized and processes data.
+pub struct Active;
+
+impl private::Sealed for Unkeyed {}
+impl private::Sealed for Keyed {}
+impl private::Sealed for Active {}
+impl State for Unkeyed {}
+impl State for Keyed {}
+impl State for Active {}
+
+/// The states in which the operation is in the initial state and accepts a key.
+pub trait Idle: State {}
+
+impl Idle for Unkeyed {}
+impl Idle for Keyed {}
+
+// Check `key` against the operation before `TEE_SetOperationKey`, which panics on a mismatch.
+fn check_key<T: ObjHandle>(info: &OperationInfo, key: &T) -> Result<()> {
+    if info.handle_state().contains(HandleFlag::EXPECT_TWO_KEYS) {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    let key_info = key.info()?;
+    if !key_info.handle_flags().contains(HandleFlag::INITIALIZED) {
+        return Err(Error::new(ErrorKind::BadState));
+    }
+    if key_info.object_size() > info.max_key_size() as usize {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    if !key_info.object_usage().contains(info.required_key_usage()) {
+        return Err(Error::new(ErrorKind::AccessDenied));
+    }
+    Ok(())
+}
+
+fn check_mode(mode: &OperationMode) -> Result<()> {
+    match mode {
+        OperationMode::Encrypt | OperationMode::Decrypt => Ok(()),
+        _ => Err(Error::new(ErrorKind::BadParameters)),
+    }
+}
+
+/// A [Cipher](../crypto_op/struct.Cipher.html) operation in state `S`.
+pub struct Cipher<S: State> {
+    operation: crypto_op::Cipher,
+    state: PhantomData<S>,
+}
+
+impl<S: State> Cipher<S> {
+    fn into_state<N: State>(self) -> Cipher<N> {
+        Cipher {
+            operation: self.operation,
+            state: PhantomData,
+        }
+    }
+
+    /// Return the characteristics of the operation.
+    pub fn info(&self) -> OperationInfo {
+        self.operation.info()
+    }
+
+    /// Return the underlying operation, which is no longer checked.
+    pub fn into_inner(self) -> crypto_op::Cipher {
+        self.operation
+    }
+}
+
+impl Cipher<Unkeyed> {
+    /// Allocate a cipher operation, see
+    /// [Cipher::allocate](../crypto_op/struct.Cipher.html#method.allocate).
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `mode` is neither [Encrypt](OperationMode::Encrypt) nor
+    ///    [Decrypt](OperationMode::Decrypt).
+    /// 2) `OutOfMemory`: If there are not enough resources to allocate the operation.
+    /// 3) `NotSupported`: If the mode is not compatible with the algorithm or key size.
+    pub fn allocate(algo: AlgorithmId, mode: OperationMode, max_key_size: usize) -> Result<Self> {
+        check_mode(&mode)?;
+        Ok(Self {
+            operation: crypto_op::Cipher::allocate(algo, mode, max_key_size)?,
+            state: PhantomData,
+        })
+    }
+}
+
+impl<S: Idle> Cipher<S> {
+    /// Program the key of the operation.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadState`: If `key` is not initialized.
+    /// 2) `BadParameters`: If `key` is bigger than the maximum key size of the operation, or
+    ///    the algorithm expects two keys.
+    /// 3) `AccessDenied`: If the usage of `key` does not allow the mode of the operation.
+    /// 4) `CorruptObject`: If the object is corrupt. The object handle is closed.
+    /// 5) `StorageNotAvailable`: If the object is stored in a storage area which is
+    ///    currently inaccessible.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the type of `key` is not compatible with the algorithm.
+    pub fn set_key<T: ObjHandle>(self, key: &T) -> Result<Cipher<Keyed>> {
+        check_key(&self.info(), key)?;
+        self.operation.set_key(key)?;
+        Ok(self.into_state())
+    }
+
+    /// Program the two keys of an [AesXts](../crypto_op/enum.AlgorithmId.html#variant.AesXts)
+    /// operation, see [set_key](Cipher::set_key) for the checks done on each key.
+    pub fn set_key_2<T: ObjHandle, D: ObjHandle>(
+        self,
+        key1: &T,
+        key2: &D,
+    ) -> Result<Cipher<Keyed>> {
+        let info = self.info();
+        if !info.handle_state().contains(HandleFlag::EXPECT_TWO_KEYS) {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        for key in [key1.info()?, key2.info()?].iter() {
+            if !key.handle_flags().contains(HandleFlag::INITIALIZED) {
+                return Err(Error::new(ErrorKind::BadState));
+            }
+            if !key.object_usage().contains(info.required_key_usage()) {
+                return Err(Error::new(ErrorKind::AccessDenied));
+            }
+        }
+        self.operation.set_key_2(key1, key2)?;
+        Ok(self.into_state())
+    }
+}
+
+impl Cipher<Keyed> {
+    /// Start the operation, see [Cipher::init](../crypto_op/struct.Cipher.html#method.init).
+    ///
+    /// # Panics
+    ///
+    /// 1) If the IV does not have the length required by the algorithm.
+    pub fn init(self, iv: &[u8]) -> Cipher<Active> {
+        self.operation.init(iv);
+        self.into_state()
+    }
+}
+
+impl Cipher<Active> {
+    /// Encrypt or decrypt the source data, see
+    /// [Cipher::update](../crypto_op/struct.Cipher.html#method.update).
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If the output buffer is not large enough to contain the output.
+    pub fn update(&mut self, src: &[u8], dest: &mut [u8]) -> Result<usize> {
+        self.operation.update(src, dest)
+    }
+
+    /// Finalize the operation and return it to the [Keyed](Keyed) state with the length of
+    /// the output, see [Cipher::do_final](../crypto_op/struct.Cipher.html#method.do_final).
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If the output buffer is not large enough to contain the output. The
+    ///    operation is returned still active.
+    pub fn do_final(
+        self,
+        src: &[u8],
+        dest: &mut [u8],
+    ) -> FinalResult<(Cipher<Keyed>, usize), Cipher<Active>> {
+        match self.operation.do_final(src, dest) {
+            Ok(len) => Ok((self.into_state(), len)),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Abandon the operation and return it to the [Keyed](Keyed) state.
+    pub fn reset(mut self) -> Cipher<Keyed> {
+        self.operation.reset();
+        self.into_state()
+    }
+}
+
+/// A [Mac](../crypto_op/struct.Mac.html) operation in state `S`.
+pub struct Mac<S: State> {
+    operation: crypto_op::Mac,
+    state: PhantomData<S>,
+}
+
+impl<S: State> Mac<S> {
+    fn into_state<N: State>(self) -> Mac<N> {
+        Mac {
+            operation: self.operation,
+            state: PhantomData,
+        }
+    }
+
+    /// Return the characteristics of the operation.
+    pub fn info(&self) -> OperationInfo {
+        self.operation.info()
+    }
+
+    /// Return the underlying operation, which is no longer checked.
+    pub fn into_inner(self) -> crypto_op::Mac {
+        self.operation
+    }
+}
+
+impl Mac<Unkeyed> {
+    /// Allocate a MAC operation, see
+    /// [Mac::allocate](../crypto_op/struct.Mac.html#method.allocate).
+    pub fn allocate(algo: AlgorithmId, max_key_size: usize) -> Result<Self> {
+        Ok(Self {
+            operation: crypto_op::Mac::allocate(algo, max_key_size)?,
+            state: PhantomData,
+        })
+    }
+}
+
+impl<S: Idle> Mac<S> {
+    /// Program the key of the operation, see [Cipher::set_key](Cipher::set_key) for the
+    /// checks and errors.
+    pub fn set_key<T: ObjHandle>(self, key: &T) -> Result<Mac<Keyed>> {
+        check_key(&self.info(), key)?;
+        self.operation.set_key(key)?;
+        Ok(self.into_state())
+    }
+}
+
+impl Mac<Keyed> {
+    /// Start the operation, see [Mac::init](../crypto_op/struct.Mac.html#method.init).
+    ///
+    /// # Panics
+    ///
+    /// 1) If the IV does not have the length required by the algorithm.
+    pub fn init(self, iv: &[u8]) -> Mac<Active> {
+        self.operation.init(iv);
+        self.into_state()
+    }
+}
+
+impl Mac<Active> {
+    /// Accumulate data for the MAC calculation.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.operation.update(chunk)
+    }
+
+    /// Finalize the operation, compute the MAC and return the operation to the
+    /// [Keyed](Keyed) state with the length of the MAC.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If the output buffer is not large enough to contain the MAC. The
+    ///    operation is returned still active.
+    pub fn compute_final(
+        self,
+        message: &[u8],
+        mac: &mut [u8],
+    ) -> FinalResult<(Mac<Keyed>, usize), Mac<Active>> {
+        match self.operation.compute_final(message, mac) {
+            Ok(len) => Ok((self.into_state(), len)),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Finalize the operation, compare the MAC with `mac` and return the operation to the
+    /// [Keyed](Keyed) state.
+    ///
+    /// # Errors
+    ///
+    /// 1) `MacInvalid`: If the computed MAC does not match `mac`. The operation is returned
+    ///    in the [Keyed](Keyed) state.
+    pub fn compare_final(self, message: &[u8], mac: &[u8]) -> FinalResult<Mac<Keyed>, Mac<Keyed>> {
+        let result = self.operation.compare_final(message, mac);
+        let operation = self.into_state();
+        match result {
+            Ok(()) => Ok(operation),
+            Err(e) => Err((operation, e)),
+        }
+    }
+
+    /// Abandon the operation and return it to the [Keyed](Keyed) state.
+    pub fn reset(mut self) -> Mac<Keyed> {
+        self.operation.reset();
+        self.into_state()
+    }
+}
+
+/// An [AE](../crypto_op/struct.AE.html) operation in state `S`.
+pub struct AE<S: State> {
+    operation: crypto_op::AE,
+    state: PhantomData<S>,
+}
+
+impl<S: State> AE<S> {
+    fn into_state<N: State>(self) -> AE<N> {
+        AE {
+            operation: self.operation,
+            state: PhantomData,
+        }
+    }
+
+    /// Return the characteristics of the operation.
+    pub fn info(&self) -> OperationInfo {
+        self.operation.info()
+    }
+
+    /// Return the underlying operation, which is no longer checked.
+    pub fn into_inner(self) -> crypto_op::AE {
+        self.operation
+    }
+}
+
+impl AE<Unkeyed> {
+    /// Allocate an authenticated encryption operation, see
+    /// [AE::allocate](../crypto_op/struct.AE.html#method.allocate).
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `mode` is neither [Encrypt](OperationMode::Encrypt) nor
+    ///    [Decrypt](OperationMode::Decrypt).
+    /// 2) `OutOfMemory`: If there are not enough resources to allocate the operation.
+    /// 3) `NotSupported`: If the mode is not compatible with the algorithm or key size.
+    pub fn allocate(algo: AlgorithmId, mode: OperationMode, max_key_size: usize) -> Result<Self> {
+        check_mode(&mode)?;
+        Ok(Self {
+            operation: crypto_op::AE::allocate(algo, mode, max_key_size)?,
+            state: PhantomData,
+        })
+    }
+}
+
+impl<S: Idle> AE<S> {
+    /// Program the key of the operation, see [Cipher::set_key](Cipher::set_key) for the
+    /// checks and errors.
+    pub fn set_key<T: ObjHandle>(self, key: &T) -> Result<AE<Keyed>> {
+        check_key(&self.info(), key)?;
+        self.operation.set_key(key)?;
+        Ok(self.into_state())
+    }
+}
+
+impl AE<Keyed> {
+    /// Start the operation, see [AE::init](../crypto_op/struct.AE.html#method.init).
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If `tag_len` is not supported by the algorithm. The operation is
+    ///    freed.
+    pub fn init(
+        self,
+        nonce: &[u8],
+        tag_len: usize,
+        aad_len: usize,
+        payload_len: usize,
+    ) -> Result<AE<Active>> {
+        self.operation.init(nonce, tag_len, aad_len, payload_len)?;
+        Ok(self.into_state())
+    }
+}
+
+impl AE<Active> {
+    /// Feed a chunk of additional authenticated data, all of it must be given before the
+    /// first call to [update](AE::update).
+    pub fn update_aad(&mut self, aad: &[u8]) {
+        self.operation.update_aad(aad)
+    }
+
+    /// Encrypt or decrypt the source data.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If the output buffer is not large enough to contain the output.
+    pub fn update(&mut self, src: &[u8], dest: &mut [u8]) -> Result<usize> {
+        self.operation.update(src, dest)
+    }
+
+    /// Finalize an encryption, returning the operation to the [Keyed](Keyed) state with the
+    /// lengths of the output and of the tag.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If `dest` or `tag` is not large enough. The operation is returned
+    ///    still active.
+    pub fn encrypt_final(
+        self,
+        src: &[u8],
+        dest: &mut [u8],
+        tag: &mut [u8],
+    ) -> FinalResult<(AE<Keyed>, usize, usize), AE<Active>> {
+        match self.operation.encrypt_final(src, dest, tag) {
+            Ok((len, tag_len)) => Ok((self.into_state(), len, tag_len)),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Finalize a decryption and check the tag, returning the operation to the
+    /// [Keyed](Keyed) state with the length of the output.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If `dest` is not large enough, see
+    ///    [DecryptFinalError](DecryptFinalError).
+    /// 2) `MacInvalid`: If the computed tag does not match `tag`.
+    pub fn decrypt_final(
+        self,
+        src: &[u8],
+        dest: &mut [u8],
+        tag: &[u8],
+    ) -> std::result::Result<(AE<Keyed>, usize), DecryptFinalError> {
+        match self.operation.decrypt_final(src, dest, tag) {
+            Ok(len) => Ok((self.into_state(), len)),
+            Err(e) if e.kind() == ErrorKind::ShortBuffer => {
+                Err(DecryptFinalError::ShortBuffer(self))
+            }
+            Err(_) => Err(DecryptFinalError::MacInvalid(self.into_state())),
+        }
+    }
+
+    /// Abandon the operation and return it to the [Keyed](Keyed) state.
+    pub fn reset(mut self) -> AE<Keyed> {
+        self.operation.reset();
+        self.into_state()
+    }
+}
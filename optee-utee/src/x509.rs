@@ -0,0 +1,992 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! X.509 certificate parsing and chain verification.
+//!
+//! Certificates signed with RSASSA-PKCS1-v1_5, RSASSA-PSS or ECDSA on the NIST curves,
+//! combined with SHA-1 or SHA-2, are verified with the TEE through
+//! [Asymmetric::verify_digest](../crypto_op/struct.Asymmetric.html#method.verify_digest).
+//! The `basicConstraints` and `keyUsage` extensions are enforced on issuers, and a
+//! certificate with any other extension marked critical is rejected.
+//!
+//! PKCS#10 certification requests are built with [CertificationRequest](CertificationRequest)
+//! and signed inside the TEE, so a key pair generated in the TA never has to leave it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use optee_utee::x509::{self, Certificate};
+//!
+//! let root = Certificate::from_pem(PINNED_ROOT)?;
+//! let chain = [Certificate::from_der(&leaf_der)?, Certificate::from_der(&intermediate_der)?];
+//! let mut now = Time::new();
+//! now.ree_time();
+//! let firmware_key = x509::verify_chain(&chain, &root, &now)?;
+//! ```
+
+use crate::der::{self, Reader};
+use crate::key;
+use crate::{
+    AlgorithmId, Asymmetric, AttributeId, AttributeValue, Digest, Error, ErrorKind, ObjHandle,
+    OperationMode, PublicKeyFormat, Result, Time, TransientObject, TransientObjectType,
+    UtcDateTime,
+};
+use bitflags::bitflags;
+use std::str;
+
+const OID_SHA1: &[u8] = &[0x2B, 0x0E, 0x03, 0x02, 0x1A];
+const OID_SHA224: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x04];
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_SHA384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const OID_MGF1: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x08];
+const OID_RSASSA_PSS: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0A];
+// sha1WithRSAEncryption and friends share this prefix, the last arc selects the hash.
+const OID_PKCS1_PREFIX: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01];
+const OID_ECDSA_WITH_SHA1: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x01];
+// ecdsa-with-SHA224 and friends share this prefix, the last arc selects the hash.
+const OID_ECDSA_SHA2_PREFIX: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1D, 0x13];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x0F];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1D, 0x11];
+const OID_EXTENSION_REQUEST: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x0E];
+
+bitflags! {
+    /// Usages of the certified key, as listed in the `keyUsage` extension.
+    pub struct KeyUsage: u16 {
+        /// Verifying signatures other than on certificates and CRLs.
+        const DIGITAL_SIGNATURE = 1 << 0;
+        /// Verifying signatures for non-repudiation services.
+        const CONTENT_COMMITMENT = 1 << 1;
+        /// Enciphering private or secret keys.
+        const KEY_ENCIPHERMENT = 1 << 2;
+        /// Enciphering raw user data.
+        const DATA_ENCIPHERMENT = 1 << 3;
+        /// Key agreement.
+        const KEY_AGREEMENT = 1 << 4;
+        /// Verifying signatures on certificates.
+        const KEY_CERT_SIGN = 1 << 5;
+        /// Verifying signatures on certificate revocation lists.
+        const CRL_SIGN = 1 << 6;
+        /// With key agreement, only enciphering data.
+        const ENCIPHER_ONLY = 1 << 7;
+        /// With key agreement, only deciphering data.
+        const DECIPHER_ONLY = 1 << 8;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Hash {
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Hash {
+    fn from_oid(oid: &[u8]) -> Result<Self> {
+        match oid {
+            OID_SHA1 => Ok(Hash::Sha1),
+            OID_SHA224 => Ok(Hash::Sha224),
+            OID_SHA256 => Ok(Hash::Sha256),
+            OID_SHA384 => Ok(Hash::Sha384),
+            OID_SHA512 => Ok(Hash::Sha512),
+            _ => Err(Error::new(ErrorKind::NotSupported)),
+        }
+    }
+
+    // Parse the AlgorithmIdentifier of a hash function, the parameters are NULL or absent.
+    fn from_algorithm(algorithm: &[u8]) -> Result<Self> {
+        der::parse(algorithm, |r| {
+            let mut seq = r.sequence()?;
+            let hash = Hash::from_oid(seq.oid()?)?;
+            seq.optional(der::TAG_NULL)?;
+            seq.finish()?;
+            Ok(hash)
+        })
+    }
+
+    fn from_digest(digest: AlgorithmId) -> Result<Self> {
+        match digest {
+            AlgorithmId::Sha1 => Ok(Hash::Sha1),
+            AlgorithmId::Sha224 => Ok(Hash::Sha224),
+            AlgorithmId::Sha256 => Ok(Hash::Sha256),
+            AlgorithmId::Sha384 => Ok(Hash::Sha384),
+            AlgorithmId::Sha512 => Ok(Hash::Sha512),
+            _ => Err(Error::new(ErrorKind::NotSupported)),
+        }
+    }
+
+    fn digest(&self) -> AlgorithmId {
+        match self {
+            Hash::Sha1 => AlgorithmId::Sha1,
+            Hash::Sha224 => AlgorithmId::Sha224,
+            Hash::Sha256 => AlgorithmId::Sha256,
+            Hash::Sha384 => AlgorithmId::Sha384,
+            Hash::Sha512 => AlgorithmId::Sha512,
+        }
+    }
+
+    fn pkcs1(&self) -> AlgorithmId {
+        match self {
+            Hash::Sha1 => AlgorithmId::RsassaPkcs1V15Sha1,
+            Hash::Sha224 => AlgorithmId::RsassaPkcs1V15Sha224,
+            Hash::Sha256 => AlgorithmId::RsassaPkcs1V15Sha256,
+            Hash::Sha384 => AlgorithmId::RsassaPkcs1V15Sha384,
+            Hash::Sha512 => AlgorithmId::RsassaPkcs1V15Sha512,
+        }
+    }
+
+    fn pss(&self) -> AlgorithmId {
+        match self {
+            Hash::Sha1 => AlgorithmId::RsassaPkcs1PssMgf1Sha1,
+            Hash::Sha224 => AlgorithmId::RsassaPkcs1PssMgf1Sha224,
+            Hash::Sha256 => AlgorithmId::RsassaPkcs1PssMgf1Sha256,
+            Hash::Sha384 => AlgorithmId::RsassaPkcs1PssMgf1Sha384,
+            Hash::Sha512 => AlgorithmId::RsassaPkcs1PssMgf1Sha512,
+        }
+    }
+
+    // The OBJECT IDENTIFIER of ECDSA with this hash.
+    fn ecdsa_oid(&self) -> Vec<u8> {
+        match self {
+            Hash::Sha1 => OID_ECDSA_WITH_SHA1.to_vec(),
+            Hash::Sha224 => [OID_ECDSA_SHA2_PREFIX, &[0x01]].concat(),
+            Hash::Sha256 => [OID_ECDSA_SHA2_PREFIX, &[0x02]].concat(),
+            Hash::Sha384 => [OID_ECDSA_SHA2_PREFIX, &[0x03]].concat(),
+            Hash::Sha512 => [OID_ECDSA_SHA2_PREFIX, &[0x04]].concat(),
+        }
+    }
+}
+
+enum SignatureScheme {
+    Pkcs1(Hash),
+    Pss { hash: Hash, salt_len: u32 },
+    Ecdsa(Hash),
+}
+
+impl SignatureScheme {
+    fn parse(algorithm: &[u8]) -> Result<Self> {
+        der::parse(algorithm, |r| {
+            let mut seq = r.sequence()?;
+            let oid = seq.oid()?;
+            let scheme = if oid == OID_RSASSA_PSS {
+                let params = seq.sequence()?;
+                Self::parse_pss(params)?
+            } else if oid.len() == OID_PKCS1_PREFIX.len() + 1 && oid.starts_with(OID_PKCS1_PREFIX) {
+                seq.optional(der::TAG_NULL)?;
+                SignatureScheme::Pkcs1(match oid[oid.len() - 1] {
+                    0x05 => Hash::Sha1,
+                    0x0E => Hash::Sha224,
+                    0x0B => Hash::Sha256,
+                    0x0C => Hash::Sha384,
+                    0x0D => Hash::Sha512,
+                    _ => return Err(Error::new(ErrorKind::NotSupported)),
+                })
+            } else if oid == OID_ECDSA_WITH_SHA1 {
+                SignatureScheme::Ecdsa(Hash::Sha1)
+            } else if oid.len() == OID_ECDSA_SHA2_PREFIX.len() + 1
+                && oid.starts_with(OID_ECDSA_SHA2_PREFIX)
+            {
+                SignatureScheme::Ecdsa(match oid[oid.len() - 1] {
+                    0x01 => Hash::Sha224,
+                    0x02 => Hash::Sha256,
+                    0x03 => Hash::Sha384,
+                    0x04 => Hash::Sha512,
+                    _ => return Err(Error::new(ErrorKind::NotSupported)),
+                })
+            } else {
+                return Err(Error::new(ErrorKind::NotSupported));
+            };
+            seq.finish()?;
+            Ok(scheme)
+        })
+    }
+
+    // RSASSA-PSS-params, RFC 4055. The TEE uses the same hash for MGF1 and the message.
+    fn parse_pss(mut params: Reader) -> Result<Self> {
+        let hash = match params.optional(der::context(0))? {
+            Some(algorithm) => Hash::from_algorithm(algorithm)?,
+            None => Hash::Sha1,
+        };
+        let mgf_hash = match params.optional(der::context(1))? {
+            Some(mgf) => der::parse(mgf, |r| {
+                let mut seq = r.sequence()?;
+                if seq.oid()? != OID_MGF1 {
+                    return Err(Error::new(ErrorKind::NotSupported));
+                }
+                let hash = seq.expect_encoded(der::TAG_SEQUENCE)?;
+                seq.finish()?;
+                Hash::from_algorithm(hash)
+            })?,
+            None => Hash::Sha1,
+        };
+        if mgf_hash != hash {
+            return Err(Error::new(ErrorKind::NotSupported));
+        }
+        let salt_len = match params.optional(der::context(2))? {
+            Some(salt_len) => der::parse(salt_len, |r| r.small_unsigned())?,
+            None => 20,
+        };
+        if let Some(trailer) = params.optional(der::context(3))? {
+            if der::parse(trailer, |r| r.small_unsigned())? != 1 {
+                return Err(Error::new(ErrorKind::NotSupported));
+            }
+        }
+        params.finish()?;
+        Ok(SignatureScheme::Pss { hash, salt_len })
+    }
+
+    fn hash(&self) -> Hash {
+        match self {
+            SignatureScheme::Pkcs1(hash) => *hash,
+            SignatureScheme::Pss { hash, .. } => *hash,
+            SignatureScheme::Ecdsa(hash) => *hash,
+        }
+    }
+}
+
+/// A parsed X.509 v1 to v3 certificate.
+pub struct Certificate {
+    der: Vec<u8>,
+    tbs: (usize, usize),
+    version: u32,
+    serial_number: Vec<u8>,
+    issuer: Vec<u8>,
+    subject: Vec<u8>,
+    not_before: u64,
+    not_after: u64,
+    public_key_info: Vec<u8>,
+    scheme: SignatureScheme,
+    signature: Vec<u8>,
+    basic_constraints: Option<(bool, Option<u32>)>,
+    key_usage: Option<KeyUsage>,
+    unknown_critical: bool,
+}
+
+impl Certificate {
+    /// Parse a DER encoded certificate.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `der`: The DER encoded certificate.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let cert = Certificate::from_der(&der)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `der` is not a well formed certificate.
+    /// 2) `NotSupported`: If the signature algorithm is not supported.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let (tbs, scheme, signature) = der::parse(der, |r| {
+            let mut seq = r.sequence()?;
+            let tbs = seq.expect_encoded(der::TAG_SEQUENCE)?;
+            let algorithm = seq.expect_encoded(der::TAG_SEQUENCE)?;
+            let signature = seq.bit_string()?;
+            seq.finish()?;
+            Ok((tbs, algorithm, signature))
+        })?;
+        let tbs_start = tbs.as_ptr() as usize - der.as_ptr() as usize;
+        let mut cert = der::parse(tbs, |r| {
+            let mut seq = r.sequence()?;
+            let version = match seq.optional(der::context(0))? {
+                Some(version) => der::parse(version, |v| v.small_unsigned())?,
+                None => 0,
+            };
+            let serial_number = seq.expect(der::TAG_INTEGER)?.to_vec();
+            let inner_algorithm = seq.expect_encoded(der::TAG_SEQUENCE)?;
+            let issuer = seq.expect_encoded(der::TAG_SEQUENCE)?.to_vec();
+            let mut validity = seq.sequence()?;
+            let not_before = parse_time(&mut validity)?;
+            let not_after = parse_time(&mut validity)?;
+            validity.finish()?;
+            let subject = seq.expect_encoded(der::TAG_SEQUENCE)?.to_vec();
+            let public_key_info = seq.expect_encoded(der::TAG_SEQUENCE)?.to_vec();
+            // issuerUniqueID and subjectUniqueID
+            seq.optional(0x81)?;
+            seq.optional(0x82)?;
+            let mut cert = Certificate {
+                der: der.to_vec(),
+                tbs: (tbs_start, tbs_start + tbs.len()),
+                version: version + 1,
+                serial_number,
+                issuer,
+                subject,
+                not_before,
+                not_after,
+                public_key_info,
+                scheme: SignatureScheme::parse(scheme)?,
+                signature: signature.to_vec(),
+                basic_constraints: None,
+                key_usage: None,
+                unknown_critical: false,
+            };
+            // The signature algorithm is repeated inside the signed data.
+            if inner_algorithm != scheme {
+                return Err(Error::new(ErrorKind::BadFormat));
+            }
+            if let Some(extensions) = seq.optional(der::context(3))? {
+                der::parse(extensions, |r| cert.parse_extensions(r.sequence()?))?;
+            }
+            seq.finish()?;
+            Ok(cert)
+        })?;
+        cert.der.shrink_to_fit();
+        Ok(cert)
+    }
+
+    /// Parse a PEM encoded certificate, the first `CERTIFICATE` block of `pem` is used.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat`: If `pem` holds no `CERTIFICATE` block or the certificate is malformed.
+    /// 2) `NotSupported`: If the signature algorithm is not supported.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let (label, der) = der::pem_decode(pem)?;
+        if label != "CERTIFICATE" {
+            return Err(Error::new(ErrorKind::BadFormat));
+        }
+        Self::from_der(&der)
+    }
+
+    fn parse_extensions(&mut self, mut extensions: Reader) -> Result<()> {
+        while !extensions.is_empty() {
+            let mut extension = extensions.sequence()?;
+            let oid = extension.oid()?;
+            let critical = extension.default_false()?;
+            let value = extension.octet_string()?;
+            extension.finish()?;
+            match oid {
+                OID_BASIC_CONSTRAINTS => {
+                    self.basic_constraints = Some(der::parse(value, |r| {
+                        let mut seq = r.sequence()?;
+                        let ca = seq.default_false()?;
+                        let path_len = match seq.peek_tag() {
+                            Some(der::TAG_INTEGER) => Some(seq.small_unsigned()?),
+                            _ => None,
+                        };
+                        seq.finish()?;
+                        Ok((ca, path_len))
+                    })?);
+                }
+                OID_KEY_USAGE => {
+                    let (bits, _) = der::parse(value, |r| r.bits())?;
+                    // Bit 0 is the most significant bit of the first byte.
+                    let mut usage = 0u16;
+                    for i in 0..9 {
+                        if bits
+                            .get(i / 8)
+                            .map_or(false, |b| b & (0x80 >> (i % 8)) != 0)
+                        {
+                            usage |= 1 << i;
+                        }
+                    }
+                    self.key_usage = Some(KeyUsage::from_bits_truncate(usage));
+                }
+                _ => self.unknown_critical |= critical,
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the DER encoding of the certificate.
+    pub fn as_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Return the DER encoding of the signed part of the certificate, `tbsCertificate`.
+    pub fn tbs_certificate(&self) -> &[u8] {
+        &self.der[self.tbs.0..self.tbs.1]
+    }
+
+    /// Return the version of the certificate, 1 to 3.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Return the serial number as the content octets of the DER INTEGER.
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    /// Return the DER encoding of the issuer `Name`.
+    pub fn issuer(&self) -> &[u8] {
+        &self.issuer
+    }
+
+    /// Return the DER encoding of the subject `Name`.
+    pub fn subject(&self) -> &[u8] {
+        &self.subject
+    }
+
+    /// Return the start of the validity period in seconds since the Unix epoch.
+    pub fn not_before(&self) -> u64 {
+        self.not_before
+    }
+
+    /// Return the end of the validity period in seconds since the Unix epoch.
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+
+    /// Return the DER encoding of the `SubjectPublicKeyInfo`.
+    pub fn public_key_info(&self) -> &[u8] {
+        &self.public_key_info
+    }
+
+    /// Return the signature value. For ECDSA it is the DER encoded `Ecdsa-Sig-Value`.
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Return whether the `basicConstraints` extension marks the subject as a CA.
+    pub fn is_ca(&self) -> bool {
+        self.basic_constraints.map_or(false, |(ca, _)| ca)
+    }
+
+    /// Return the `pathLenConstraint` of the `basicConstraints` extension.
+    pub fn path_len_constraint(&self) -> Option<u32> {
+        self.basic_constraints.and_then(|(_, path_len)| path_len)
+    }
+
+    /// Return the `keyUsage` extension, `None` if the certificate does not restrict the usage.
+    pub fn key_usage(&self) -> Option<KeyUsage> {
+        self.key_usage
+    }
+
+    /// Load the public key of the subject into a transient object, see
+    /// [TransientObject::from_der](../object/struct.TransientObject.html#method.from_der).
+    pub fn public_key(&self) -> Result<TransientObject> {
+        TransientObject::from_der(&self.public_key_info)
+    }
+
+    /// Check that `now` is within the validity period of the certificate.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `now`: The current time in seconds since the Unix epoch, as returned by
+    ///    [Time::ree_time](../time/struct.Time.html#method.ree_time) or a TA persistent time
+    ///    kept in Unix time.
+    ///
+    /// # Errors
+    ///
+    /// 1) `Security`: If the certificate is not yet valid or has expired.
+    pub fn check_validity(&self, now: &Time) -> Result<()> {
+        let now = now.seconds as u64;
+        if now < self.not_before || now > self.not_after {
+            return Err(Error::new(ErrorKind::Security));
+        }
+        Ok(())
+    }
+
+    /// Verify the signature of the certificate with the public key of `issuer`.
+    ///
+    /// Only the signature is checked, see [verify_chain](fn.verify_chain.html) for the
+    /// complete validation of a path.
+    ///
+    /// # Errors
+    ///
+    /// 1) `SignatureInvalid`: If the signature does not verify or the key of `issuer` does not
+    ///    match the signature algorithm.
+    /// 2) `NotSupported`: If the key of `issuer` is not supported.
+    /// 3) `OutOfMemory`: If not enough resources are available to allocate the operations.
+    pub fn verify_signature(&self, issuer: &Certificate) -> Result<()> {
+        let key = issuer.public_key()?;
+        let info = key.info()?;
+        let key_size = info.object_size();
+        let is_rsa = info.object_type() == TransientObjectType::RsaPublicKey as u32;
+        let hash = self.scheme.hash();
+
+        let digest = Digest::allocate(hash.digest())?;
+        let mut tbs_hash = [0u8; 64];
+        let hash_len = digest.do_final(self.tbs_certificate(), &mut tbs_hash)?;
+        let tbs_hash = &tbs_hash[..hash_len];
+
+        let (algorithm, params, signature) = match &self.scheme {
+            SignatureScheme::Pkcs1(_) if is_rsa => (hash.pkcs1(), vec![], self.signature.clone()),
+            SignatureScheme::Pss { salt_len, .. } if is_rsa => {
+                let salt = AttributeValue::from_value(AttributeId::RsaPssSaltLength, *salt_len, 0);
+                (hash.pss(), vec![salt.into()], self.signature.clone())
+            }
+            SignatureScheme::Ecdsa(_) if !is_rsa => {
+                let algorithm = match key_size {
+                    192 => AlgorithmId::EcdsaP192,
+                    224 => AlgorithmId::EcdsaP224,
+                    256 => AlgorithmId::EcdsaP256,
+                    384 => AlgorithmId::EcdsaP384,
+                    521 => AlgorithmId::EcdsaP521,
+                    _ => return Err(Error::new(ErrorKind::NotSupported)),
+                };
+                let signature = ecdsa_signature(&self.signature, (key_size + 7) / 8)?;
+                (algorithm, vec![], signature)
+            }
+            _ => return Err(Error::new(ErrorKind::SignatureInvalid)),
+        };
+        let operation = Asymmetric::allocate(algorithm, OperationMode::Verify, key_size)?;
+        operation.set_key(&key)?;
+        operation.verify_digest(&params, tbs_hash, &signature)
+    }
+
+    // Check that the certificate may issue a certificate with `below` intermediate CA
+    // certificates under it.
+    fn check_issuer(&self, below: usize) -> Result<()> {
+        let may_sign = self
+            .key_usage
+            .map_or(true, |usage| usage.contains(KeyUsage::KEY_CERT_SIGN));
+        let path_ok = self
+            .path_len_constraint()
+            .map_or(true, |path_len| below <= path_len as usize);
+        if self.is_ca() && may_sign && path_ok {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::Security))
+        }
+    }
+
+    fn check_critical(&self) -> Result<()> {
+        if self.unknown_critical {
+            Err(Error::new(ErrorKind::Security))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Verify a certificate chain up to a pinned root and return the public key of the leaf.
+///
+/// Every certificate, the root included, must be valid at `now` and have no unknown critical
+/// extension. Each certificate must be signed by the next one, the last by `root`, with
+/// matching issuer and subject names. Issuers must be CAs per `basicConstraints`, allow
+/// `keyCertSign` if they carry `keyUsage`, and satisfy their `pathLenConstraint`. The root is
+/// trusted as given, its own signature is not checked.
+///
+/// # Parameters
+///
+/// 1) `chain`: The leaf certificate followed by the intermediate CA certificates, in order.
+/// 2) `root`: The pinned trust anchor.
+/// 3) `now`: The current time, see [Certificate::check_validity](Certificate::check_validity).
+///
+/// # Example
+///
+/// ```no_run
+/// let mut now = Time::new();
+/// now.ree_time();
+/// let key = verify_chain(&[leaf, intermediate], &root, &now)?;
+/// ```
+///
+/// # Errors
+///
+/// 1) `BadParameters`: If `chain` is empty.
+/// 2) `Security`: If a certificate is outside its validity period, has an unknown critical
+///    extension, or an issuer is not allowed to sign certificates.
+/// 3) `SignatureInvalid`: If a signature does not verify.
+/// 4) `NotSupported`: If a key or algorithm is not supported.
+pub fn verify_chain(
+    chain: &[Certificate],
+    root: &Certificate,
+    now: &Time,
+) -> Result<TransientObject> {
+    let leaf = chain
+        .first()
+        .ok_or_else(|| Error::new(ErrorKind::BadParameters))?;
+    root.check_validity(now)?;
+    root.check_critical()?;
+    for (i, cert) in chain.iter().enumerate() {
+        let issuer = chain.get(i + 1).unwrap_or(root);
+        cert.check_validity(now)?;
+        cert.check_critical()?;
+        if cert.issuer != issuer.subject {
+            return Err(Error::new(ErrorKind::Security));
+        }
+        issuer.check_issuer(i)?;
+        cert.verify_signature(issuer)?;
+    }
+    leaf.public_key()
+}
+
+/// An attribute type of a distinguished name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameAttribute {
+    /// `commonName`, 2.5.4.3.
+    CommonName,
+    /// `serialNumber`, 2.5.4.5, encoded as a PrintableString.
+    SerialNumber,
+    /// `countryName`, 2.5.4.6, a two letter ISO 3166 code encoded as a PrintableString.
+    Country,
+    /// `localityName`, 2.5.4.7.
+    Locality,
+    /// `stateOrProvinceName`, 2.5.4.8.
+    State,
+    /// `organizationName`, 2.5.4.10.
+    Organization,
+    /// `organizationalUnitName`, 2.5.4.11.
+    OrganizationalUnit,
+}
+
+impl NameAttribute {
+    fn oid(&self) -> [u8; 3] {
+        let arc = match self {
+            NameAttribute::CommonName => 3,
+            NameAttribute::SerialNumber => 5,
+            NameAttribute::Country => 6,
+            NameAttribute::Locality => 7,
+            NameAttribute::State => 8,
+            NameAttribute::Organization => 10,
+            NameAttribute::OrganizationalUnit => 11,
+        };
+        [0x55, 0x04, arc]
+    }
+}
+
+/// A distinguished name, built one attribute at a time in the order of the relative
+/// distinguished names.
+#[derive(Clone, Debug, Default)]
+pub struct Name {
+    rdns: Vec<Vec<u8>>,
+}
+
+impl Name {
+    /// Create an empty name.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a relative distinguished name holding a single attribute.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `attribute`: The attribute type.
+    /// 2) `value`: The attribute value.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut subject = Name::new();
+    /// subject
+    ///     .add(NameAttribute::Country, "FR")?
+    ///     .add(NameAttribute::Organization, "Example")?
+    ///     .add(NameAttribute::CommonName, "device-0001")?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `value` is empty, or for the attributes encoded as a
+    ///    PrintableString, holds characters outside of that set or is not a two letter country.
+    pub fn add(&mut self, attribute: NameAttribute, value: &str) -> Result<&mut Self> {
+        let printable = |c: char| c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(c);
+        let tag = match attribute {
+            NameAttribute::Country if value.len() != 2 || !value.chars().all(printable) => {
+                return Err(Error::new(ErrorKind::BadParameters))
+            }
+            NameAttribute::SerialNumber if !value.chars().all(printable) => {
+                return Err(Error::new(ErrorKind::BadParameters))
+            }
+            NameAttribute::Country | NameAttribute::SerialNumber => der::TAG_PRINTABLE_STRING,
+            _ => der::TAG_UTF8_STRING,
+        };
+        if value.is_empty() {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        let attribute = der::sequence(&[
+            &der::oid(&attribute.oid()),
+            &der::encode(tag, value.as_bytes()),
+        ]);
+        self.rdns.push(der::encode(der::TAG_SET, &attribute));
+        Ok(self)
+    }
+
+    /// Return the DER encoding of the name, as compared with
+    /// [Certificate::subject](Certificate::subject).
+    pub fn to_der(&self) -> Vec<u8> {
+        der::encode(der::TAG_SEQUENCE, &self.rdns.concat())
+    }
+}
+
+/// A certificate extension requested in a [CertificationRequest](CertificationRequest).
+#[derive(Clone, Debug)]
+pub struct Extension {
+    der: Vec<u8>,
+}
+
+impl Extension {
+    /// Create an extension from its object identifier and DER encoded value.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `oid`: The extension identifier in dotted form, e.g. `"2.5.29.37"`.
+    /// 2) `critical`: Whether the extension is marked critical.
+    /// 3) `value`: The DER encoding of the extension value, without the wrapping OCTET STRING.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `oid` is not a valid object identifier.
+    pub fn new(oid: &str, critical: bool, value: &[u8]) -> Result<Self> {
+        Ok(Self::from_oid(&encode_oid(oid)?, critical, value))
+    }
+
+    /// Create a `basicConstraints` extension, marked critical.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `ca`: Whether the certified key may sign certificates.
+    /// 2) `path_len`: The maximum number of intermediate CAs below, only encoded when `ca` is set.
+    pub fn basic_constraints(ca: bool, path_len: Option<u32>) -> Self {
+        let mut value = Vec::new();
+        if ca {
+            value.extend(der::boolean(true));
+            if let Some(path_len) = path_len {
+                value.extend(der::unsigned(&path_len.to_be_bytes()));
+            }
+        }
+        Self::from_oid(
+            OID_BASIC_CONSTRAINTS,
+            true,
+            &der::encode(der::TAG_SEQUENCE, &value),
+        )
+    }
+
+    /// Create a `keyUsage` extension, marked critical.
+    pub fn key_usage(usage: KeyUsage) -> Self {
+        // Bit 0 is the most significant bit of the first byte, trailing zero bits are dropped.
+        let bits = usage.bits();
+        let value = match (0..16).rev().find(|i| bits & (1 << i) != 0) {
+            Some(last) => {
+                let mut bytes = vec![0u8; last / 8 + 1];
+                for i in 0..=last {
+                    if bits & (1 << i) != 0 {
+                        bytes[i / 8] |= 0x80 >> (i % 8);
+                    }
+                }
+                der::bits(&bytes, 7 - (last % 8) as u8)
+            }
+            None => der::bits(&[], 0),
+        };
+        Self::from_oid(OID_KEY_USAGE, true, &value)
+    }
+
+    /// Create a non critical `subjectAltName` extension listing DNS names.
+    pub fn subject_alt_name(dns_names: &[&str]) -> Self {
+        let names: Vec<Vec<u8>> = dns_names
+            .iter()
+            .map(|name| der::encode(0x82, name.as_bytes()))
+            .collect();
+        Self::from_oid(
+            OID_SUBJECT_ALT_NAME,
+            false,
+            &der::encode(der::TAG_SEQUENCE, &names.concat()),
+        )
+    }
+
+    fn from_oid(oid: &[u8], critical: bool, value: &[u8]) -> Self {
+        let critical = if critical {
+            der::boolean(true)
+        } else {
+            Vec::new()
+        };
+        Self {
+            der: der::sequence(&[&der::oid(oid), &critical, &der::octet_string(value)]),
+        }
+    }
+}
+
+/// A signed PKCS#10 certification request, RFC 2986.
+///
+/// The request is signed inside the TEE with the private key of the subject, only the public
+/// key and the signature are part of the encoding.
+pub struct CertificationRequest {
+    der: Vec<u8>,
+}
+
+impl CertificationRequest {
+    /// Build and sign a certification request.
+    ///
+    /// RSA keys sign with `sha256WithRSAEncryption`. ECDSA keys sign with
+    /// `ecdsa-with-SHA256` up to 256 bits, `ecdsa-with-SHA384` for P-384 and
+    /// `ecdsa-with-SHA512` for P-521.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `subject`: The subject name.
+    /// 2) `extensions`: The extensions to request, sent as a PKCS#9 `extensionRequest`.
+    /// 3) `key`: A transient or persistent [RsaKeypair](TransientObjectType::RsaKeypair) or
+    ///    [EcdsaKeypair](TransientObjectType::EcdsaKeypair) object whose usage allows signing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = TransientObject::allocate(TransientObjectType::EcdsaKeypair, 256)?;
+    /// key.generate_key(256, &[AttributeValue::from_curve(ElementId::EccCurveNistP256).into()])?;
+    /// let mut subject = Name::new();
+    /// subject.add(NameAttribute::CommonName, "device-0001")?;
+    /// let extensions = [Extension::key_usage(KeyUsage::DIGITAL_SIGNATURE)];
+    /// let csr = CertificationRequest::new(&subject, &extensions, &key)?;
+    /// let pem = csr.to_pem();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If `key` is not an RSA or ECDSA key pair.
+    /// 2) `OutOfMemory`: If not enough resources are available to allocate the operations.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the usage of `key` does not allow signing.
+    pub fn new<T: ObjHandle>(subject: &Name, extensions: &[Extension], key: &T) -> Result<Self> {
+        let info = key.info()?;
+        let key_size = info.object_size();
+        let object_type = info.object_type();
+        let (algorithm, hash, signature_oid) =
+            if object_type == TransientObjectType::RsaKeypair as u32 {
+                (
+                    AlgorithmId::RsassaPkcs1V15Sha256,
+                    Hash::Sha256,
+                    [OID_PKCS1_PREFIX, &[0x0B]].concat(),
+                )
+            } else if object_type == TransientObjectType::EcdsaKeypair as u32 {
+                let algorithm = match key_size {
+                    192 => AlgorithmId::EcdsaP192,
+                    224 => AlgorithmId::EcdsaP224,
+                    256 => AlgorithmId::EcdsaP256,
+                    384 => AlgorithmId::EcdsaP384,
+                    521 => AlgorithmId::EcdsaP521,
+                    _ => return Err(Error::new(ErrorKind::NotSupported)),
+                };
+                // The same digest as the signers of rustcrypto, for the same key.
+                let digest = algorithm
+                    .ecdsa_digest()
+                    .ok_or_else(|| Error::new(ErrorKind::NotSupported))?;
+                let hash = Hash::from_digest(digest)?;
+                (algorithm, hash, hash.ecdsa_oid())
+            } else {
+                return Err(Error::new(ErrorKind::NotSupported));
+            };
+        let is_rsa = object_type == TransientObjectType::RsaKeypair as u32;
+
+        let public_key_info = key::public_key_der(key, PublicKeyFormat::Spki)?;
+        let attributes = if extensions.is_empty() {
+            Vec::new()
+        } else {
+            let requested: Vec<&[u8]> = extensions.iter().map(|e| e.der.as_slice()).collect();
+            let extensions = der::sequence(&requested);
+            der::sequence(&[
+                &der::oid(OID_EXTENSION_REQUEST),
+                &der::encode(der::TAG_SET, &extensions),
+            ])
+        };
+        let info = der::sequence(&[
+            &der::unsigned(&[0]),
+            &subject.to_der(),
+            &public_key_info,
+            &der::encode(der::context(0), &attributes),
+        ]);
+
+        let digest = Digest::allocate(hash.digest())?;
+        let mut info_hash = [0u8; 64];
+        let hash_len = digest.do_final(&info, &mut info_hash)?;
+        let operation = Asymmetric::allocate(algorithm, OperationMode::Sign, key_size)?;
+        operation.set_key(key)?;
+        let key_len = (key_size + 7) / 8;
+        let mut signature = vec![0u8; if is_rsa { key_len } else { 2 * key_len }];
+        let len = operation.sign_digest(&[], &info_hash[..hash_len], &mut signature)?;
+        signature.truncate(len);
+
+        let algorithm = if is_rsa {
+            der::sequence(&[&der::oid(&signature_oid), &der::null()])
+        } else {
+            // Ecdsa-Sig-Value, the TEE returns `r || s`.
+            let (r, s) = signature.split_at(len / 2);
+            signature = der::sequence(&[&der::unsigned(r), &der::unsigned(s)]);
+            der::sequence(&[&der::oid(&signature_oid)])
+        };
+        Ok(Self {
+            der: der::sequence(&[&info, &algorithm, &der::bit_string(&signature)]),
+        })
+    }
+
+    /// Return the DER encoding of the request.
+    pub fn as_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Return the PEM encoding of the request, labelled `CERTIFICATE REQUEST`.
+    pub fn to_pem(&self) -> String {
+        der::pem_encode("CERTIFICATE REQUEST", &self.der)
+    }
+}
+
+// Encode the content octets of an OBJECT IDENTIFIER given in dotted form.
+fn encode_oid(oid: &str) -> Result<Vec<u8>> {
+    let arcs = oid
+        .split('.')
+        .map(|arc| arc.parse::<u64>())
+        .collect::<core::result::Result<Vec<u64>, _>>()
+        .map_err(|_| Error::new(ErrorKind::BadParameters))?;
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] > 39) {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    let mut res = Vec::new();
+    for arc in core::iter::once(arcs[0] * 40 + arcs[1]).chain(arcs[2..].iter().copied()) {
+        // Base 128, most significant group first, all but the last with the high bit set.
+        let mut groups = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest != 0 {
+            groups.push((rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+        res.extend(groups.iter().rev());
+    }
+    Ok(res)
+}
+
+// Convert an Ecdsa-Sig-Value to the `r || s` form expected by the TEE.
+fn ecdsa_signature(signature: &[u8], len: usize) -> Result<Vec<u8>> {
+    let (r, s) = der::parse(signature, |r| {
+        let mut seq = r.sequence()?;
+        let rs = (seq.unsigned()?, seq.unsigned()?);
+        seq.finish()?;
+        Ok(rs)
+    })?;
+    if r.len() > len || s.len() > len {
+        return Err(Error::new(ErrorKind::SignatureInvalid));
+    }
+    let mut res = vec![0u8; 2 * len];
+    res[len - r.len()..len].copy_from_slice(r);
+    res[2 * len - s.len()..].copy_from_slice(s);
+    Ok(res)
+}
+
+// Parse a UTCTime or GeneralizedTime in the forms allowed by RFC 5280, to seconds since the
+// Unix epoch.
+fn parse_time(r: &mut Reader) -> Result<u64> {
+    let tlv = r.read()?;
+    let value = str::from_utf8(tlv.value).map_err(|_| Error::new(ErrorKind::BadFormat))?;
+    let time = match (tlv.tag, value.len()) {
+        (der::TAG_UTC_TIME, 13) => UtcDateTime::parse_utc_time(value)?,
+        // RFC 5280 forbids fractional seconds
+        (der::TAG_GENERALIZED_TIME, 15) => UtcDateTime::parse_generalized_time(value)?,
+        _ => return Err(Error::new(ErrorKind::BadFormat)),
+    };
+    match time.unix_seconds() {
+        secs if secs < 0 => Err(Error::new(ErrorKind::NotSupported)),
+        secs => Ok(secs as u64),
+    }
+}
@@ -26,10 +26,12 @@ fn main() {
     cfg.target("aarch64-unknown-linux-gnu")
         .header("tee_api_types.h")
         .header("tee_api_defines.h")
+        .header("tee_api_defines_extensions.h")
         .header("utee_types.h")
         .header("user_ta_header.h")
         .header("tee_api.h")
         .header("utee_syscalls.h")
+        .header("trace_levels.h")
         .include(env::var("OPTEE_OS_INCLUDE").unwrap())
         .type_name(|s, _is_struct, _is_union| {
             if s == "utee_params"